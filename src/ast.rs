@@ -0,0 +1,300 @@
+use byteorder::ByteOrder;
+
+use crate::error::BBScriptError;
+use crate::game_config::{CodeBlock, ScriptConfig};
+use crate::parser::{ArgValue, InstructionValue};
+use crate::rebuilder::rebuild_from_instructions;
+
+/// A script arranged as a tree of blocks using the [`CodeBlock`] of each instruction.
+///
+/// Top level blocks are typically states or subroutines,
+/// which can in turn contain nested blocks such as `if` statements
+#[derive(Debug, Clone, Default)]
+pub struct ScriptTree {
+    pub nodes: Vec<ScriptNode>,
+}
+
+// instructions are already large because of their inline args, so blocks are left unboxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ScriptNode {
+    Instruction(InstructionValue),
+    Block(ScriptBlock),
+}
+
+/// Instructions enclosed by a [`CodeBlock::Begin`] and [`CodeBlock::End`] instruction
+#[derive(Debug, Clone)]
+pub struct ScriptBlock {
+    pub begin: InstructionValue,
+    pub body: Vec<ScriptNode>,
+    pub end: InstructionValue,
+}
+
+impl ScriptBlock {
+    /// Returns the name of the block, which is the first string argument of its `begin` instruction
+    pub fn name(&self) -> Option<&str> {
+        self.begin.args.iter().find_map(|arg| match arg {
+            ArgValue::String32(s) => Some(s.0.as_str()),
+            ArgValue::String16(s) => Some(s.0.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over blocks directly contained in this block
+    pub fn blocks(&self) -> impl Iterator<Item = &ScriptBlock> {
+        self.body.iter().filter_map(ScriptNode::as_block)
+    }
+
+    /// Returns a mutable iterator over blocks directly contained in this block
+    pub fn blocks_mut(&mut self) -> impl Iterator<Item = &mut ScriptBlock> {
+        self.body.iter_mut().filter_map(ScriptNode::as_block_mut)
+    }
+}
+
+impl ScriptNode {
+    pub fn as_block(&self) -> Option<&ScriptBlock> {
+        match self {
+            ScriptNode::Block(block) => Some(block),
+            ScriptNode::Instruction(_) => None,
+        }
+    }
+
+    pub fn as_block_mut(&mut self) -> Option<&mut ScriptBlock> {
+        match self {
+            ScriptNode::Block(block) => Some(block),
+            ScriptNode::Instruction(_) => None,
+        }
+    }
+}
+
+impl ScriptTree {
+    /// Builds a tree from a flat list of instructions,
+    /// returning an error if any `Begin` and `End` instructions are unbalanced
+    pub fn from_instructions(
+        program: impl IntoIterator<Item = InstructionValue>,
+    ) -> Result<Self, BBScriptError> {
+        // each open block keeps its index in the program for error reporting
+        let mut open_blocks: Vec<(usize, InstructionValue, Vec<ScriptNode>)> = Vec::new();
+        let mut nodes = Vec::new();
+
+        for (index, instruction) in program.into_iter().enumerate() {
+            match instruction.code_block {
+                CodeBlock::Begin => open_blocks.push((index, instruction, Vec::new())),
+                CodeBlock::End => {
+                    let (_, begin, body) = open_blocks.pop().ok_or_else(|| {
+                        BBScriptError::UnmatchedBlockEnd(instruction.display_name(), index)
+                    })?;
+
+                    let block = ScriptNode::Block(ScriptBlock {
+                        begin,
+                        body,
+                        end: instruction,
+                    });
+
+                    match open_blocks.last_mut() {
+                        Some((_, _, parent)) => parent.push(block),
+                        None => nodes.push(block),
+                    }
+                }
+                _ => {
+                    let node = ScriptNode::Instruction(instruction);
+
+                    match open_blocks.last_mut() {
+                        Some((_, _, parent)) => parent.push(node),
+                        None => nodes.push(node),
+                    }
+                }
+            }
+        }
+
+        if let Some((index, begin, _)) = open_blocks.pop() {
+            return Err(BBScriptError::UnclosedBlock(begin.display_name(), index));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Returns an iterator over the top level blocks of the script
+    pub fn states(&self) -> impl Iterator<Item = &ScriptBlock> {
+        self.nodes.iter().filter_map(ScriptNode::as_block)
+    }
+
+    /// Returns a mutable iterator over the top level blocks of the script
+    pub fn states_mut(&mut self) -> impl Iterator<Item = &mut ScriptBlock> {
+        self.nodes.iter_mut().filter_map(ScriptNode::as_block_mut)
+    }
+
+    /// Finds the first top level block with the given name
+    pub fn state(&self, name: &str) -> Option<&ScriptBlock> {
+        self.states().find(|state| state.name() == Some(name))
+    }
+
+    /// Finds the first top level block with the given name
+    pub fn state_mut(&mut self, name: &str) -> Option<&mut ScriptBlock> {
+        self.states_mut().find(|state| state.name() == Some(name))
+    }
+
+    /// Visits every instruction in script order along with how deeply it is nested in blocks.
+    /// The `begin` and `end` instructions of a block are visited at the depth of the block itself
+    pub fn walk<F: FnMut(usize, &InstructionValue)>(&self, mut visit: F) {
+        fn walk_nodes<F: FnMut(usize, &InstructionValue)>(
+            nodes: &[ScriptNode],
+            depth: usize,
+            visit: &mut F,
+        ) {
+            for node in nodes {
+                match node {
+                    ScriptNode::Instruction(instruction) => visit(depth, instruction),
+                    ScriptNode::Block(block) => {
+                        visit(depth, &block.begin);
+                        walk_nodes(&block.body, depth + 1, visit);
+                        visit(depth, &block.end);
+                    }
+                }
+            }
+        }
+
+        walk_nodes(&self.nodes, 0, &mut visit)
+    }
+
+    /// Visits every instruction mutably in script order, see [`ScriptTree::walk`]
+    pub fn walk_mut<F: FnMut(usize, &mut InstructionValue)>(&mut self, mut visit: F) {
+        fn walk_nodes<F: FnMut(usize, &mut InstructionValue)>(
+            nodes: &mut [ScriptNode],
+            depth: usize,
+            visit: &mut F,
+        ) {
+            for node in nodes {
+                match node {
+                    ScriptNode::Instruction(instruction) => visit(depth, instruction),
+                    ScriptNode::Block(block) => {
+                        visit(depth, &mut block.begin);
+                        walk_nodes(&mut block.body, depth + 1, visit);
+                        visit(depth, &mut block.end);
+                    }
+                }
+            }
+        }
+
+        walk_nodes(&mut self.nodes, 0, &mut visit)
+    }
+
+    /// Flattens the tree back into a list of instructions in script order
+    pub fn into_instructions(self) -> Vec<InstructionValue> {
+        fn flatten(nodes: Vec<ScriptNode>, out: &mut Vec<InstructionValue>) {
+            for node in nodes {
+                match node {
+                    ScriptNode::Instruction(instruction) => out.push(instruction),
+                    ScriptNode::Block(block) => {
+                        out.push(block.begin);
+                        flatten(block.body, out);
+                        out.push(block.end);
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        flatten(self.nodes, &mut out);
+
+        out
+    }
+
+    /// Writes the tree in the readable format produced by [`ScriptConfig::parse_to_string`]
+    pub fn to_readable_string(
+        &self,
+        config: &ScriptConfig,
+        indent_limit: usize,
+    ) -> Result<String, BBScriptError> {
+        let mut program = Vec::new();
+        self.walk(|_, instruction| program.push(instruction.clone()));
+
+        config.instructions_to_string(&program, indent_limit)
+    }
+
+    /// Assembles the tree into a script usable by games
+    pub fn to_bytes<B: ByteOrder>(&self, config: &ScriptConfig) -> Result<Vec<u8>, BBScriptError> {
+        let mut program = Vec::new();
+        self.walk(|_, instruction| program.push(instruction.clone()));
+
+        rebuild_from_instructions::<B>(config, &program)
+    }
+}
+
+impl ScriptConfig {
+    /// Parses a script into a [`ScriptTree`]
+    pub fn parse_tree<B: ByteOrder>(
+        &self,
+        input: impl AsRef<[u8]>,
+    ) -> Result<ScriptTree, BBScriptError> {
+        ScriptTree::from_instructions(self.parse::<B>(input)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScriptTree;
+    use crate::game_config::{ScriptConfig, SizedString};
+    use crate::parser::ArgValue;
+    use crate::rebuilder::rebuild_bbscript;
+    use crate::BBScriptError;
+    use byteorder::LittleEndian;
+
+    const SCRIPT: &str = "beginState: s32'StateA'
+  beginSubroutine: s32'Inner'
+  endSubroutine:
+endState:
+beginState: s32'StateB'
+endState:
+";
+
+    #[test]
+    fn tree_round_trip() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, SCRIPT.into()).unwrap();
+
+        let mut tree = config.parse_tree::<LittleEndian>(&bytes).unwrap();
+        assert_eq!(tree.states().count(), 2);
+
+        let state = tree.state("StateA").unwrap();
+        assert_eq!(state.blocks().next().unwrap().name(), Some("Inner"));
+
+        assert_eq!(tree.to_bytes::<LittleEndian>(&config).unwrap(), bytes);
+        assert_eq!(
+            tree.to_readable_string(&config, 12).unwrap(),
+            config.parse_to_string::<LittleEndian>(&bytes, 12).unwrap()
+        );
+
+        let state = tree.state_mut("StateB").unwrap();
+        state.begin.args[0] = ArgValue::String32(SizedString("StateC".into()));
+
+        let renamed = config
+            .parse_tree::<LittleEndian>(tree.to_bytes::<LittleEndian>(&config).unwrap())
+            .unwrap();
+        assert!(renamed.state("StateB").is_none());
+        assert!(renamed.state("StateC").is_some());
+    }
+
+    #[test]
+    fn unbalanced_blocks() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, "beginState: s32'A'".into()).unwrap();
+        let program = config.parse::<LittleEndian>(bytes).unwrap();
+        assert!(matches!(
+            ScriptTree::from_instructions(program),
+            Err(BBScriptError::UnclosedBlock(_, 0))
+        ));
+
+        let bytes = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'\nendState:\nendState:".into(),
+        )
+        .unwrap();
+        let program = config.parse::<LittleEndian>(bytes).unwrap();
+        assert!(matches!(
+            ScriptTree::from_instructions(program),
+            Err(BBScriptError::UnmatchedBlockEnd(_, 2))
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use rayon::prelude::*;
+
+use bbscript::BBScriptError;
+
+/// A single input file and the path its output should be written to
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub input: PathBuf,
+    pub output: PathBuf,
+}
+
+/// Expands a list of files, directories and glob patterns into jobs whose outputs mirror
+/// the input layout inside of `output_dir`.
+///
+/// Files inside a directory keep their path relative to that directory, files matched by a glob
+/// keep their path relative to the part of the pattern before its first wildcard,
+/// and files given directly keep their path relative to the deepest directory they all share
+pub fn collect_jobs(inputs: &[PathBuf], output_dir: &Path) -> Result<Vec<BatchJob>, BBScriptError> {
+    Ok(collect_files(inputs)?
+        .into_iter()
+        .map(|(input, relative)| BatchJob {
+            input,
+            output: output_dir.join(relative),
+        })
+        .collect())
+}
+
+/// Expands a list of files, directories and glob patterns into the files they contain,
+/// each paired with the path used for its output relative to an output directory
+pub fn collect_files(inputs: &[PathBuf]) -> Result<Vec<(PathBuf, PathBuf)>, BBScriptError> {
+    let mut files = Vec::new();
+
+    let canonical = |input: &Path| {
+        input
+            .canonicalize()
+            .map_err(|_| BBScriptError::BadInputFile(input.to_string_lossy().into()))
+    };
+    let direct_files = inputs
+        .iter()
+        .filter(|input| input.is_file())
+        .map(|input| canonical(input))
+        .collect::<Result<Vec<_>, _>>()?;
+    let direct_root = common_ancestor(&direct_files);
+
+    for input in inputs {
+        if input.is_dir() {
+            for entry in walkdir::WalkDir::new(input)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+            {
+                // walkdir always yields paths prefixed by the root
+                let relative = entry.path().strip_prefix(input).unwrap();
+
+                files.push((entry.path().to_path_buf(), relative.to_path_buf()));
+            }
+        } else if input.is_file() {
+            let relative = relative_path(&canonical(input)?, &direct_root);
+            files.push((input.clone(), relative));
+        } else {
+            let pattern = input.to_string_lossy();
+            let paths = glob::glob(&pattern)
+                .map_err(|_| BBScriptError::BadInputFile(pattern.to_string()))?
+                .filter_map(|p| p.ok())
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>();
+
+            if paths.is_empty() {
+                return Err(BBScriptError::BadInputFile(pattern.to_string()));
+            }
+
+            let root = glob_root(input);
+            files.extend(paths.into_iter().map(|path| {
+                let relative = relative_path(&path, &root);
+                (path, relative)
+            }));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Returns the leading components of a glob pattern, up to the first one containing a wildcard
+fn glob_root(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+        .collect()
+}
+
+/// Returns the deepest directory containing every one of the files
+fn common_ancestor(files: &[PathBuf]) -> PathBuf {
+    let mut parents = files.iter().filter_map(|file| file.parent());
+    let Some(first) = parents.next() else {
+        return PathBuf::new();
+    };
+
+    parents.fold(first.to_path_buf(), |ancestor, parent| {
+        ancestor
+            .components()
+            .zip(parent.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect()
+    })
+}
+
+/// Returns the path of a file relative to `root`, or only its file name if it isn't inside of `root`
+fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+
+    if relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        relative.to_path_buf()
+    } else {
+        // `is_file` was checked by the caller so there is always a file name
+        PathBuf::from(path.file_name().unwrap())
+    }
+}
+
+/// Returns an error if more than one job would write to the same output
+pub fn check_duplicate_outputs(jobs: &[BatchJob]) -> Result<(), BBScriptError> {
+    let mut outputs = HashMap::new();
+
+    for job in jobs {
+        if let Some(other) = outputs.insert(&job.output, &job.input) {
+            return Err(BBScriptError::DuplicateOutput(
+                other.to_string_lossy().into(),
+                job.input.to_string_lossy().into(),
+                job.output.to_string_lossy().into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `process` on every job in parallel, then prints a report of which files succeeded or failed.
+/// Every job is run even if some of them fail, but none are run if two jobs share an output
+pub fn run_jobs<F>(jobs: &[BatchJob], overwrite: bool, process: F) -> Result<(), BBScriptError>
+where
+    F: Fn(&Path, &Path) -> anyhow::Result<()> + Sync,
+{
+    check_duplicate_outputs(jobs)?;
+
+    let results: Vec<anyhow::Result<()>> = jobs
+        .par_iter()
+        .map(|job| {
+            if job.output.exists() && !overwrite {
+                return Err(BBScriptError::OutputAlreadyExists(
+                    job.output.to_string_lossy().into(),
+                )
+                .into());
+            }
+
+            if let Some(parent) = job.output.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            process(&job.input, &job.output)
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (job, result) in jobs.iter().zip(results) {
+        match result {
+            Ok(()) => println!("OK: {}", job.input.display()),
+            Err(e) => {
+                failed += 1;
+                println!("FAILED: {}: {e}", job.input.display());
+            }
+        }
+    }
+
+    let total = jobs.len();
+    println!("{} of {total} files processed successfully", total - failed);
+
+    if failed > 0 {
+        return Err(BBScriptError::BatchFailed(failed, total));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{check_duplicate_outputs, collect_jobs};
+
+    fn outputs(inputs: &[&str]) -> Vec<PathBuf> {
+        let inputs: Vec<PathBuf> = inputs.iter().map(PathBuf::from).collect();
+        collect_jobs(&inputs, Path::new("out"))
+            .unwrap()
+            .into_iter()
+            .map(|job| job.output)
+            .collect()
+    }
+
+    #[test]
+    fn mirror_input_layout() {
+        // directories keep paths relative to themselves
+        let from_dir = outputs(&["src"]);
+        assert!(from_dir.contains(&PathBuf::from("out/main.rs")));
+        assert!(from_dir.contains(&PathBuf::from("out/readable_bbscript.pest")));
+
+        // globs keep paths relative to the part before the first wildcard
+        assert_eq!(
+            outputs(&["static_db/ggst.r?n"]),
+            [PathBuf::from("out/ggst.ron")]
+        );
+        assert_eq!(
+            outputs(&["stat*/ggst.ron"]),
+            [PathBuf::from("out/static_db/ggst.ron")]
+        );
+
+        // files given directly keep paths relative to the directory they share
+        assert_eq!(outputs(&["src/lib.rs"]), [PathBuf::from("out/lib.rs")]);
+        assert_eq!(
+            outputs(&["src/lib.rs", "static_db/ggst.ron"]),
+            [
+                PathBuf::from("out/src/lib.rs"),
+                PathBuf::from("out/static_db/ggst.ron")
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_outputs() {
+        let inputs = [
+            PathBuf::from("static_db/ggst.ron"),
+            PathBuf::from("static_db"),
+        ];
+        let jobs = collect_jobs(&inputs, Path::new("out")).unwrap();
+
+        assert_eq!(
+            check_duplicate_outputs(&jobs).unwrap_err().to_string(),
+            "Inputs `static_db/ggst.ron` and `static_db/ggst.ron` would both be written to `out/ggst.ron`"
+        );
+        assert!(check_duplicate_outputs(&jobs[1..]).is_ok());
+    }
+}
@@ -0,0 +1,567 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use bimap::BiMap;
+use smallvec::SmallVec;
+
+use crate::game_config::{
+    ArgInfo, ArgType, BBSNumber, GenericInstruction, Instruction, InstructionInfo, ScriptConfig,
+};
+
+/// A difference in a single entry between two configs, identified by its key
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K, V> {
+    Added(K, V),
+    Removed(K, V),
+    /// The entry exists in both configs, with the old value followed by the new one
+    Changed(K, V, V),
+}
+
+/// A difference in an enum of `named_value_maps` between two configs
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnumDiff {
+    Added(String),
+    Removed(String),
+    /// The enum exists in both configs, with the differences between its variants
+    Changed(String, Vec<Change<BBSNumber, String>>),
+}
+
+/// Differences between an old and a new config, with every list ordered by key
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub instructions: Vec<Change<u32, GenericInstruction>>,
+    pub enums: Vec<EnumDiff>,
+    pub named_variables: Vec<Change<BBSNumber, String>>,
+}
+
+impl ConfigDiff {
+    /// Compares the instructions, enums and named variables of two configs
+    pub fn new(old: &ScriptConfig, new: &ScriptConfig) -> Self {
+        let ids: BTreeSet<u32> = old
+            .instructions
+            .iter_generic()
+            .chain(new.instructions.iter_generic())
+            .map(|(id, _)| id)
+            .collect();
+
+        let instructions = ids
+            .into_iter()
+            .filter_map(|id| match (old.get_by_id(id), new.get_by_id(id)) {
+                (Some(old), Some(new)) if old != new => Some(Change::Changed(id, old, new)),
+                (Some(old), None) => Some(Change::Removed(id, old)),
+                (None, Some(new)) => Some(Change::Added(id, new)),
+                _ => None,
+            })
+            .collect();
+
+        let enum_names: BTreeSet<&String> = old
+            .named_value_maps
+            .keys()
+            .chain(new.named_value_maps.keys())
+            .collect();
+
+        let enums = enum_names
+            .into_iter()
+            .filter_map(|name| {
+                match (
+                    old.named_value_maps.get(name),
+                    new.named_value_maps.get(name),
+                ) {
+                    (Some(old), Some(new)) => {
+                        let changes = diff_bimap(old, new);
+                        (!changes.is_empty()).then(|| EnumDiff::Changed(name.clone(), changes))
+                    }
+                    (Some(_), None) => Some(EnumDiff::Removed(name.clone())),
+                    (None, Some(_)) => Some(EnumDiff::Added(name.clone())),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+
+        Self {
+            instructions,
+            enums,
+            named_variables: diff_bimap(&old.named_variables, &new.named_variables),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty() && self.enums.is_empty() && self.named_variables.is_empty()
+    }
+}
+
+/// Compares two maps of values to names by value
+fn diff_bimap(
+    old: &BiMap<BBSNumber, String>,
+    new: &BiMap<BBSNumber, String>,
+) -> Vec<Change<BBSNumber, String>> {
+    let values: BTreeSet<&BBSNumber> = old.left_values().chain(new.left_values()).collect();
+
+    values
+        .into_iter()
+        .filter_map(
+            |value| match (old.get_by_left(value), new.get_by_left(value)) {
+                (Some(old), Some(new)) if old != new => {
+                    Some(Change::Changed(*value, old.clone(), new.clone()))
+                }
+                (Some(old), None) => Some(Change::Removed(*value, old.clone())),
+                (None, Some(new)) => Some(Change::Added(*value, new.clone())),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Sizes of an instruction and of each of its args, including the bytes not covered by known args.
+/// Instructions with the same layout read the same bytes, even if their args are typed differently
+fn layout(instruction: &GenericInstruction) -> (Option<usize>, Vec<usize>) {
+    match instruction {
+        GenericInstruction::Sized(_, i) => {
+            (Some(i.size), i.args().iter().map(ArgType::size).collect())
+        }
+        GenericInstruction::Unsized(_, i) => (None, i.args.iter().map(ArgType::size).collect()),
+    }
+}
+
+fn describe_instruction(instruction: &GenericInstruction) -> String {
+    let mut result = instruction.display_name();
+
+    if let Some(size) = instruction.size() {
+        result += &format!(" size {size}");
+    }
+
+    result + &format!(" args {:?}", instruction.args())
+}
+
+/// Lists the fields that differ between two versions of an instruction
+fn describe_changes(old: &GenericInstruction, new: &GenericInstruction) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if old.name() != new.name() {
+        changes.push(format!(
+            "name {} -> {}",
+            old.display_name(),
+            new.display_name()
+        ));
+    }
+    if old.size() != new.size() {
+        let size = |i: &GenericInstruction| i.size().map_or("unsized".into(), |s| s.to_string());
+        changes.push(format!("size {} -> {}", size(old), size(new)));
+    }
+    if old.args() != new.args() {
+        changes.push(format!("args {:?} -> {:?}", old.args(), new.args()));
+    }
+    if old.block_type() != new.block_type() {
+        changes.push(format!(
+            "code block {:?} -> {:?}",
+            old.block_type(),
+            new.block_type()
+        ));
+    }
+    if old.symbol() != new.symbol() {
+        changes.push(format!("symbol {:?} -> {:?}", old.symbol(), new.symbol()));
+    }
+    if old.description() != new.description() {
+        changes.push("description".into());
+    }
+    if old.arg_info() != new.arg_info() {
+        changes.push("arg info".into());
+    }
+
+    changes
+}
+
+fn write_bimap_change(
+    f: &mut fmt::Formatter<'_>,
+    change: &Change<BBSNumber, String>,
+    indent: &str,
+) -> fmt::Result {
+    match change {
+        Change::Added(value, name) => writeln!(f, "{indent}+ {value}: {name}"),
+        Change::Removed(value, name) => writeln!(f, "{indent}- {value}: {name}"),
+        Change::Changed(value, old, new) => writeln!(f, "{indent}~ {value}: {old} -> {new}"),
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.instructions.is_empty() {
+            writeln!(f, "instructions:")?;
+        }
+        for change in &self.instructions {
+            match change {
+                Change::Added(id, new) => writeln!(f, "+ {id}: {}", describe_instruction(new))?,
+                Change::Removed(id, old) => writeln!(f, "- {id}: {}", describe_instruction(old))?,
+                Change::Changed(id, old, new) => writeln!(
+                    f,
+                    "~ {id}: {}: {}",
+                    new.display_name(),
+                    describe_changes(old, new).join(", ")
+                )?,
+            }
+        }
+
+        if !self.enums.is_empty() {
+            writeln!(f, "enums:")?;
+        }
+        for change in &self.enums {
+            match change {
+                EnumDiff::Added(name) => writeln!(f, "+ {name}")?,
+                EnumDiff::Removed(name) => writeln!(f, "- {name}")?,
+                EnumDiff::Changed(name, variants) => {
+                    writeln!(f, "~ {name}")?;
+                    for variant in variants {
+                        write_bimap_change(f, variant, "    ")?;
+                    }
+                }
+            }
+        }
+
+        if !self.named_variables.is_empty() {
+            writeln!(f, "named variables:")?;
+        }
+        for change in &self.named_variables {
+            write_bimap_change(f, change, "")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What was carried over from an old config by [`ScriptConfig::merge_from`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeReport {
+    /// Instructions that were given the name they have in the old config
+    pub names: Vec<(u32, String)>,
+    /// Instructions that were given the description they have in the old config
+    pub descriptions: Vec<u32>,
+    /// Instructions that were given the arg names and descriptions they have in the old config
+    pub arg_info: Vec<u32>,
+    /// Number args that became enum args, by instruction ID and arg index
+    pub enum_args: Vec<(u32, usize, String)>,
+    /// Enums that were added because an instruction in the merged config uses them
+    pub enums: Vec<String>,
+    /// Enums that were left out because no instruction in the merged config uses them
+    pub unused_enums: Vec<String>,
+    /// Named variables that were added
+    pub named_variables: Vec<(BBSNumber, String)>,
+    /// Instructions in both configs that were left alone because their args have a different layout
+    pub layout_mismatches: Vec<u32>,
+    /// Names that were not carried over because another instruction in the new config already uses them
+    pub name_conflicts: Vec<(u32, String)>,
+}
+
+impl ScriptConfig {
+    /// Compares this config against an older one
+    pub fn diff_from(&self, old: &ScriptConfig) -> ConfigDiff {
+        ConfigDiff::new(old, self)
+    }
+
+    /// Carries names, descriptions, arg info and enums from an older config onto this one.
+    ///
+    /// Only instructions with the same ID and the same size and arg layout in both configs are changed,
+    /// where they gain the old name, description and arg info if they have none, and number args become
+    /// the enum args they are in the old config.
+    /// Enums missing from this config are added if one of its instructions uses them, while enums already
+    /// in it are kept as they are, as any variants missing from them may have been removed on purpose.
+    /// Named variables are added when neither their value nor their name is used
+    pub fn merge_from(&mut self, old: &ScriptConfig) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        let mut used_names: HashSet<String> = self
+            .instructions
+            .iter_generic()
+            .filter_map(|(_, i)| i.name())
+            .collect();
+
+        let ids: BTreeSet<u32> = self.instructions.iter_generic().map(|(id, _)| id).collect();
+
+        for id in ids {
+            let (Some(old_instruction), Some(new_instruction)) =
+                (old.get_by_id(id), self.get_by_id(id))
+            else {
+                continue;
+            };
+
+            if layout(&old_instruction) != layout(&new_instruction) {
+                report.layout_mismatches.push(id);
+                continue;
+            }
+
+            match &mut self.instructions {
+                InstructionInfo::Sized(map) => {
+                    let instruction = map.get_mut(&id).unwrap();
+                    let mut args = SmallVec::from(Instruction::args(instruction));
+
+                    carry_instruction(
+                        id,
+                        &old_instruction,
+                        &mut instruction.name,
+                        &mut instruction.description,
+                        &mut instruction.arg_info,
+                        &mut args,
+                        &mut used_names,
+                        &mut report,
+                    );
+                    instruction.set_args(args);
+                }
+                InstructionInfo::Unsized(map) => {
+                    let instruction = map.get_mut(&id).unwrap();
+
+                    carry_instruction(
+                        id,
+                        &old_instruction,
+                        &mut instruction.name,
+                        &mut instruction.description,
+                        &mut instruction.arg_info,
+                        &mut instruction.args,
+                        &mut used_names,
+                        &mut report,
+                    );
+                }
+            }
+        }
+
+        let used_enums: HashSet<String> = self
+            .instructions
+            .iter_generic()
+            .flat_map(|(_, i)| i.args())
+            .filter_map(|arg| match arg {
+                ArgType::Enum(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        let old_enums: BTreeMap<&String, &BiMap<BBSNumber, String>> =
+            old.named_value_maps.iter().collect();
+
+        for (name, old_variants) in old_enums {
+            if self.named_value_maps.contains_key(name) {
+                continue;
+            }
+
+            if used_enums.contains(name) {
+                self.named_value_maps
+                    .insert(name.clone(), old_variants.clone());
+                report.enums.push(name.clone());
+            } else {
+                report.unused_enums.push(name.clone());
+            }
+        }
+
+        let mut new_variables = self.named_variables.clone();
+        merge_bimap(&mut new_variables, &old.named_variables);
+        report.named_variables = diff_bimap(&self.named_variables, &new_variables)
+            .into_iter()
+            .filter_map(|change| match change {
+                Change::Added(value, name) => Some((value, name)),
+                _ => None,
+            })
+            .collect();
+        self.named_variables = new_variables;
+
+        report
+    }
+}
+
+/// Adds every entry of `old` whose value and name are both unused in `new`
+fn merge_bimap(new: &mut BiMap<BBSNumber, String>, old: &BiMap<BBSNumber, String>) {
+    for (value, name) in old {
+        if !new.contains_left(value) && !new.contains_right(name) {
+            new.insert(*value, name.clone());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn carry_instruction(
+    id: u32,
+    old: &GenericInstruction,
+    name: &mut String,
+    description: &mut String,
+    arg_info: &mut Vec<ArgInfo>,
+    args: &mut SmallVec<[ArgType; 16]>,
+    used_names: &mut HashSet<String>,
+    report: &mut MergeReport,
+) {
+    if let Some(old_name) = old.name() {
+        if name.is_empty() {
+            if used_names.insert(old_name.clone()) {
+                *name = old_name.clone();
+                report.names.push((id, old_name));
+            } else {
+                report.name_conflicts.push((id, old_name));
+            }
+        }
+    }
+
+    let old_description = old.description();
+    if description.is_empty() && !old_description.is_empty() {
+        *description = old_description.to_string();
+        report.descriptions.push(id);
+    }
+
+    // the arg layouts already match, so the old arg info still lines up with the args
+    if arg_info.is_empty() && !old.arg_info().is_empty() {
+        *arg_info = old.arg_info().to_vec();
+        report.arg_info.push(id);
+    }
+
+    for (index, (arg, old_arg)) in args.iter_mut().zip(old.args()).enumerate() {
+        if let (ArgType::Number, ArgType::Enum(enum_name)) = (&arg, old_arg) {
+            *arg = old_arg.clone();
+            report.enum_args.push((id, index, enum_name.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, ConfigDiff, EnumDiff};
+    use crate::game_config::{ArgInfo, ArgType, InstructionInfo, ScriptConfig};
+
+    const OLD_CONFIG: &str = r#"(
+        jump_table_ids: [0],
+        literal_tag: 0,
+        variable_tag: 2,
+        named_variables: { 0: "Tmp", 1: "Angle" },
+        named_value_maps: {
+            "Direction": { 0: "Left", 1: "Right" },
+            "Removed": { 0: "Old" },
+        },
+        instructions: Sized({
+            0: (size: 36, name: "beginState", codeBlock: Begin, args: [String32], description: "Starts a state"),
+            1: (size: 4, name: "endState", codeBlock: End, args: []),
+            2: (size: 12, name: "turn", args: [Enum("Direction"), Number], argInfo: [(name: "to")]),
+            3: (size: 8, name: "wait", args: [Number]),
+            4: (size: 4, name: "removed", args: []),
+        }),
+    )"#;
+
+    const NEW_CONFIG: &str = r#"(
+        jump_table_ids: [0],
+        literal_tag: 0,
+        variable_tag: 2,
+        named_variables: { 0: "Tmp", 1: "Rotation", 2: "SpeedX" },
+        named_value_maps: {
+            "Direction": { 0: "Left", 2: "Up" },
+        },
+        instructions: Sized({
+            0: (size: 36, codeBlock: Begin, args: [String32]),
+            1: (size: 4, name: "endState", codeBlock: End, args: []),
+            2: (size: 12, args: [Number, Number]),
+            3: (size: 12, args: [Number]),
+            5: (size: 4, name: "turn", args: []),
+        }),
+    )"#;
+
+    fn configs() -> (ScriptConfig, ScriptConfig) {
+        (
+            ScriptConfig::new(OLD_CONFIG.as_bytes()).unwrap(),
+            ScriptConfig::new(NEW_CONFIG.as_bytes()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn diff_configs() {
+        let (old, new) = configs();
+        let diff = ConfigDiff::new(&old, &new);
+
+        let ids: Vec<_> = diff
+            .instructions
+            .iter()
+            .map(|change| match change {
+                Change::Added(id, _) => ('+', *id),
+                Change::Removed(id, _) => ('-', *id),
+                Change::Changed(id, _, _) => ('~', *id),
+            })
+            .collect();
+        assert_eq!(ids, [('~', 0), ('~', 2), ('~', 3), ('-', 4), ('+', 5)]);
+
+        assert_eq!(
+            diff.enums,
+            [
+                EnumDiff::Changed(
+                    "Direction".into(),
+                    vec![
+                        Change::Removed(1, "Right".into()),
+                        Change::Added(2, "Up".into())
+                    ]
+                ),
+                EnumDiff::Removed("Removed".into()),
+            ]
+        );
+        assert_eq!(
+            diff.named_variables,
+            [
+                Change::Changed(1, "Angle".into(), "Rotation".into()),
+                Change::Added(2, "SpeedX".into())
+            ]
+        );
+
+        let text = diff.to_string();
+        assert!(text.contains("~ 3: Unknown3: name wait -> Unknown3, size 8 -> 12\n"));
+        assert!(text.contains("~ 0: Unknown0: name beginState -> Unknown0, description\n"));
+
+        assert!(new.diff_from(&new).is_empty());
+
+        // an instruction is changed when only its arg info is
+        let mut documented = new.clone();
+        if let InstructionInfo::Sized(map) = &mut documented.instructions {
+            map.get_mut(&1).unwrap().arg_info = vec![ArgInfo {
+                name: "frames".into(),
+                ..Default::default()
+            }];
+        }
+        assert_eq!(
+            documented.diff_from(&new).to_string(),
+            "instructions:\n~ 1: endState: arg info\n"
+        );
+    }
+
+    #[test]
+    fn merge_configs() {
+        let (old, mut new) = configs();
+        let report = new.merge_from(&old);
+
+        assert_eq!(report.names, [(0, "beginState".into())]);
+        assert_eq!(report.descriptions, [0]);
+        assert_eq!(report.arg_info, [2]);
+        assert_eq!(report.enum_args, [(2, 0, "Direction".into())]);
+        assert!(report.enums.is_empty());
+        assert_eq!(report.unused_enums, ["Removed"]);
+        assert!(report.named_variables.is_empty());
+        assert_eq!(report.layout_mismatches, [3]);
+        assert_eq!(report.name_conflicts, [(2, "turn".into())]);
+
+        let begin = new.get_by_name("beginState").unwrap();
+        assert_eq!(begin.id(), 0);
+        assert_eq!(
+            new.get_by_id(2).unwrap().args(),
+            [ArgType::Enum("Direction".into()), ArgType::Number]
+        );
+        assert_eq!(new.arg_info(2)[0].name, "to");
+        // instruction 3 has a different size, so it doesn't take the old name
+        assert_eq!(new.get_by_id(3).unwrap().name(), None);
+
+        // variants the new config removed stay removed
+        let direction = &new.named_value_maps["Direction"];
+        assert_eq!(direction.len(), 2);
+        assert!(!direction.contains_right("Right"));
+        assert!(!new.named_value_maps.contains_key("Removed"));
+        // the value and name are both taken
+        assert_eq!(new.named_variables.get_by_left(&1).unwrap(), "Rotation");
+        assert!(!new.named_variables.contains_right("Angle"));
+
+        // enums missing from the new config are added if a carried enum arg uses them
+        let (old, mut new) = configs();
+        new.named_value_maps.remove("Direction");
+        let report = new.merge_from(&old);
+
+        assert_eq!(report.enums, ["Direction"]);
+        assert_eq!(report.unused_enums, ["Removed"]);
+        assert_eq!(
+            new.named_value_maps["Direction"],
+            old.named_value_maps["Direction"]
+        );
+    }
+}
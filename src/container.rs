@@ -0,0 +1,273 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BBScriptError;
+
+/// Tag found at the end of Unreal Engine packages and `.uexp` files.
+/// Like the rest of the package data around a script, it is always little-endian
+pub const PACKAGE_FILE_TAG: u32 = 0x9E2A83C1;
+
+/// Largest header that will be searched for when detecting a container
+const MAX_HEADER_SIZE: usize = 0x400;
+
+const SIZE_FIELD_LENGTH: usize = 0x4;
+
+/// Size of a single entry in a script's jump table, a 32 byte name followed by an offset
+const JUMP_ENTRY_LENGTH: usize = 0x24;
+
+/// Extension appended to the readable script's file name for the sidecar holding its container
+pub const SIDECAR_EXTENSION: &str = "container";
+
+/// Data surrounding a script inside of a file extracted from a game,
+/// such as the Unreal Engine metadata in front of scripts extracted from UPKs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Container {
+    pub header: Vec<u8>,
+    pub footer: Vec<u8>,
+    /// If the last 4 bytes of the header are the size of the script, which is updated when rebuilding
+    pub has_size_field: bool,
+}
+
+/// How a [`Container`] is stored on disk, bytes are stored as hex strings
+#[derive(Serialize, Deserialize)]
+struct ContainerFile {
+    header: String,
+    footer: String,
+    has_size_field: bool,
+}
+
+impl Container {
+    /// Attempts to find a script inside of a container.
+    ///
+    /// A container is detected when a header of up to 0x400 bytes ends in a little-endian `u32` holding the size of the script,
+    /// and the script is followed by either nothing or the Unreal package tag.
+    /// Without the package tag the script must also start with a jump table that fits inside of it,
+    /// so that a raw script that happens to contain its remaining size isn't mistaken for a container.
+    /// `B` is the byte order of the script itself, used to read its jump table.
+    /// Returns the container along with the range of the script within `file`
+    pub fn detect<B: ByteOrder>(file: &[u8]) -> Option<(Container, Range<usize>)> {
+        let tag = PACKAGE_FILE_TAG.to_le_bytes();
+
+        let footers: [&[u8]; 2] = [&[], &tag];
+
+        (SIZE_FIELD_LENGTH * 2..=MAX_HEADER_SIZE.min(file.len()))
+            .step_by(SIZE_FIELD_LENGTH)
+            .find_map(|script_start| {
+                let size = LittleEndian::read_u32(&file[script_start - SIZE_FIELD_LENGTH..]) as usize;
+
+                footers.iter().find_map(|footer| {
+                    let script_end = script_start.checked_add(size)?;
+
+                    if size != 0
+                        && script_end.checked_add(footer.len())? == file.len()
+                        && file.ends_with(footer)
+                        && (!footer.is_empty() || jump_table_fits::<B>(&file[script_start..]))
+                    {
+                        Some(script_start..script_end)
+                    } else {
+                        None
+                    }
+                })
+            })
+            .map(|range| {
+                let container = Container {
+                    header: file[..range.start].to_vec(),
+                    footer: file[range.end..].to_vec(),
+                    has_size_field: true,
+                };
+                log::info!(
+                    "Detected container with a {:#X} byte header and {:#X} byte footer around the script",
+                    container.header.len(),
+                    container.footer.len()
+                );
+
+                (container, range)
+            })
+    }
+
+    /// Creates a container from everything outside of `range`,
+    /// for when the start and end of a script are already known
+    pub fn from_range(file: &[u8], range: Range<usize>) -> Container {
+        let header = &file[..range.start];
+        let has_size_field = header.len() >= SIZE_FIELD_LENGTH
+            && LittleEndian::read_u32(&header[header.len() - SIZE_FIELD_LENGTH..]) as usize
+                == range.len();
+
+        Container {
+            header: header.to_vec(),
+            footer: file[range.end..].to_vec(),
+            has_size_field,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty() && self.footer.is_empty()
+    }
+
+    /// Surrounds a rebuilt script with the container, updating the size field if there is one
+    pub fn wrap(&self, script: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.header.len() + script.len() + self.footer.len());
+
+        result.extend_from_slice(&self.header);
+
+        if self.has_size_field {
+            let size_field = result.len() - SIZE_FIELD_LENGTH;
+            LittleEndian::write_u32(&mut result[size_field..], script.len() as u32);
+        }
+
+        result.extend_from_slice(script);
+        result.extend_from_slice(&self.footer);
+
+        result
+    }
+
+    /// Returns the path of the sidecar file used to store the container of a readable script
+    pub fn sidecar_path(script_path: impl AsRef<Path>) -> PathBuf {
+        let mut path = script_path.as_ref().as_os_str().to_owned();
+        path.push(".");
+        path.push(SIDECAR_EXTENSION);
+
+        path.into()
+    }
+
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Self, BBScriptError> {
+        let path_name = path.as_ref().to_string_lossy().to_string();
+        let file = File::open(&path)?;
+
+        let stored: ContainerFile = ron::de::from_reader(file)
+            .map_err(|e| BBScriptError::ContainerInvalid(path_name.clone(), e.to_string()))?;
+
+        let decode = |data: &str| {
+            hex::decode(data)
+                .map_err(|e| BBScriptError::ContainerInvalid(path_name.clone(), e.to_string()))
+        };
+
+        Ok(Container {
+            header: decode(&stored.header)?,
+            footer: decode(&stored.footer)?,
+            has_size_field: stored.has_size_field,
+        })
+    }
+
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<(), BBScriptError> {
+        let stored = ContainerFile {
+            header: hex::encode_upper(&self.header),
+            footer: hex::encode_upper(&self.footer),
+            has_size_field: self.has_size_field,
+        };
+
+        let serialized = ron::ser::to_string_pretty(&stored, ron::ser::PrettyConfig::default())
+            .map_err(|e| BBScriptError::ExportError(e.to_string()))?;
+
+        File::create(path)?.write_all(serialized.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Returns `true` if `script` starts with a non-zero jump table entry count whose entries fit inside of it
+fn jump_table_fits<B: ByteOrder>(script: &[u8]) -> bool {
+    if script.len() < SIZE_FIELD_LENGTH {
+        return false;
+    }
+
+    let count = B::read_u32(script) as usize;
+    count != 0
+        && count
+            .checked_mul(JUMP_ENTRY_LENGTH)
+            .and_then(|size| size.checked_add(SIZE_FIELD_LENGTH))
+            .is_some_and(|size| size <= script.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Container;
+    use byteorder::{BigEndian, LittleEndian};
+
+    #[test]
+    fn detect_and_wrap() {
+        let mut file = vec![0xAA; 0x34];
+        file.extend_from_slice(&8u32.to_le_bytes());
+        file.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+        file.extend_from_slice(&super::PACKAGE_FILE_TAG.to_le_bytes());
+
+        let (container, range) = Container::detect::<LittleEndian>(&file).unwrap();
+        assert_eq!(range, 0x38..0x40);
+        assert_eq!(container.footer, super::PACKAGE_FILE_TAG.to_le_bytes());
+
+        assert_eq!(container.wrap(&file[range]), file);
+
+        let wrapped = container.wrap(&[3, 0, 0, 0]);
+        assert_eq!(wrapped[0x34..0x38], 4u32.to_le_bytes());
+        assert_eq!(wrapped.len(), file.len() - 4);
+    }
+
+    #[test]
+    fn raw_script_has_no_container() {
+        let config = crate::SupportedGame::Ggst.into_config();
+        let script = crate::rebuilder::rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'\n  sprite: s32'a', 3\nendState:".into(),
+        )
+        .unwrap();
+
+        assert!(Container::detect::<LittleEndian>(&script).is_none());
+
+        // the sprite's duration is the number of bytes after it, which looks like a size field
+        let script = crate::rebuilder::rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'\n  sprite: s32'a', 4\nendState:".into(),
+        )
+        .unwrap();
+        assert_eq!(
+            script[script.len() - 8..script.len() - 4],
+            4u32.to_le_bytes()
+        );
+        assert!(Container::detect::<LittleEndian>(&script).is_none());
+
+        // a size field with nothing after the script needs a jump table that fits
+        let mut file = vec![0xAA; 0x8];
+        file.extend_from_slice(&0x10u32.to_le_bytes());
+        file.extend_from_slice(&[2, 0, 0, 0, 0xAA, 0xAA, 0xAA, 0xAA, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(Container::detect::<LittleEndian>(&file).is_none());
+    }
+
+    #[test]
+    fn detect_without_footer() {
+        let mut script = 1u32.to_le_bytes().to_vec();
+        script.extend_from_slice(&[0; 0x24]);
+        script.extend_from_slice(&[0, 0, 0, 0, 1, 0, 0, 0]);
+
+        let mut file = vec![0xAA; 0x10];
+        file.extend_from_slice(&(script.len() as u32).to_le_bytes());
+        file.extend_from_slice(&script);
+
+        let (container, range) = Container::detect::<LittleEndian>(&file).unwrap();
+        assert_eq!(range, 0x14..file.len());
+        assert!(container.footer.is_empty());
+    }
+
+    #[test]
+    fn big_endian_script_in_little_endian_container() {
+        let mut script = 1u32.to_be_bytes().to_vec();
+        script.extend_from_slice(&[0; 0x24]);
+        script.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let mut file = vec![0xAA; 0x10];
+        file.extend_from_slice(&(script.len() as u32).to_le_bytes());
+        file.extend_from_slice(&script);
+        file.extend_from_slice(&super::PACKAGE_FILE_TAG.to_le_bytes());
+
+        let (container, range) = Container::detect::<BigEndian>(&file).unwrap();
+        assert_eq!(range, 0x14..file.len() - 4);
+        assert_eq!(Container::from_range(&file, range.clone()), container);
+
+        let wrapped = container.wrap(&script[..0x28]);
+        assert_eq!(wrapped[0x10..0x14], 0x28u32.to_le_bytes());
+    }
+}
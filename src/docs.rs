@@ -0,0 +1,431 @@
+use std::collections::BTreeMap;
+
+use crate::game_config::{ArgType, BBSNumber, CodeBlock, GenericInstruction, ScriptConfig, Symbol};
+
+/// Formats a config can be rendered to as a reference of its instructions, args, enums and variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum DocsFormat {
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+/// A piece of text within a paragraph or table cell
+#[derive(Debug, Clone)]
+enum Span {
+    Text(String),
+    Code(String),
+    Link { text: String, anchor: String },
+}
+
+/// A block of the rendered reference, independent of the output format
+#[derive(Debug, Clone)]
+enum Block {
+    Heading {
+        level: usize,
+        text: Vec<Span>,
+        anchor: String,
+    },
+    Paragraph(Vec<Span>),
+    Table {
+        headers: Vec<&'static str>,
+        rows: Vec<Vec<Vec<Span>>>,
+    },
+}
+
+impl ScriptConfig {
+    /// Renders the config as a browsable reference titled `title`, with an index of instructions
+    /// linking to each instruction's args and description, and enum args linking to their enum
+    pub fn render_docs(&self, title: &str, format: DocsFormat) -> String {
+        let blocks = self.docs_blocks(title);
+
+        match format {
+            DocsFormat::Markdown => render_markdown(&blocks),
+            DocsFormat::Html => render_html(title, &blocks),
+        }
+    }
+
+    fn docs_blocks(&self, title: &str) -> Vec<Block> {
+        let mut ids: Vec<u32> = self.instructions.iter_generic().map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        let instructions: Vec<GenericInstruction> = ids
+            .into_iter()
+            .filter_map(|id| self.get_by_id(id))
+            .collect();
+
+        let enums: BTreeMap<_, _> = self.named_value_maps.iter().collect();
+        let variables: BTreeMap<_, _> = self.named_variables.iter().collect();
+
+        let mut blocks = vec![
+            heading(1, vec![text(title)], "top"),
+            Block::Paragraph(vec![
+                link("Instructions", "instructions"),
+                text(" · "),
+                link("Enums", "enums"),
+                text(" · "),
+                link("Variables", "variables"),
+            ]),
+            heading(2, vec![text("Instructions")], "instructions"),
+            Block::Table {
+                headers: vec!["ID", "Name", "Size", "Description"],
+                rows: instructions
+                    .iter()
+                    .map(|instruction| {
+                        vec![
+                            vec![text(instruction.id().to_string())],
+                            vec![link(
+                                instruction.display_name(),
+                                instruction_anchor(instruction.id()),
+                            )],
+                            vec![text(
+                                instruction.size().map_or("-".into(), |s| s.to_string()),
+                            )],
+                            vec![text(instruction.description().lines().next().unwrap_or(""))],
+                        ]
+                    })
+                    .collect(),
+            },
+        ];
+
+        for instruction in &instructions {
+            blocks.extend(self.instruction_blocks(instruction));
+        }
+
+        blocks.push(heading(2, vec![text("Enums")], "enums"));
+        if enums.is_empty() {
+            blocks.push(Block::Paragraph(vec![text("This config has no enums.")]));
+        }
+        for (name, variants) in enums {
+            let users: Vec<&GenericInstruction> = instructions
+                .iter()
+                .filter(|instruction| {
+                    instruction
+                        .args()
+                        .iter()
+                        .any(|arg| matches!(arg, ArgType::Enum(e) if e == name))
+                })
+                .collect();
+            let variants: BTreeMap<&BBSNumber, &String> = variants.iter().collect();
+
+            blocks.push(heading(3, vec![code(name)], enum_anchor(name)));
+            if !users.is_empty() {
+                let mut spans = vec![text("Used by ")];
+                for (index, instruction) in users.into_iter().enumerate() {
+                    if index > 0 {
+                        spans.push(text(", "));
+                    }
+                    spans.push(link(
+                        instruction.display_name(),
+                        instruction_anchor(instruction.id()),
+                    ));
+                }
+                blocks.push(Block::Paragraph(spans));
+            }
+            blocks.push(Block::Table {
+                headers: vec!["Value", "Name"],
+                rows: variants
+                    .into_iter()
+                    .map(|(value, variant)| {
+                        vec![vec![text(value.to_string())], vec![code(variant)]]
+                    })
+                    .collect(),
+            });
+        }
+
+        blocks.push(heading(2, vec![text("Variables")], "variables"));
+        if variables.is_empty() {
+            blocks.push(Block::Paragraph(vec![text(
+                "This config has no named variables.",
+            )]));
+        } else {
+            blocks.push(Block::Table {
+                headers: vec!["ID", "Name"],
+                rows: variables
+                    .into_iter()
+                    .map(|(id, name)| vec![vec![text(id.to_string())], vec![code(name)]])
+                    .collect(),
+            });
+        }
+
+        blocks
+    }
+
+    fn instruction_blocks(&self, instruction: &GenericInstruction) -> Vec<Block> {
+        let id = instruction.id();
+        let mut blocks = vec![heading(
+            3,
+            vec![code(instruction.display_name())],
+            instruction_anchor(id),
+        )];
+
+        let mut summary = format!("ID {id}");
+        match instruction.size() {
+            Some(size) => summary += &format!(", {size} bytes"),
+            None => summary += ", unsized",
+        }
+        match instruction.block_type() {
+            CodeBlock::Begin => summary += ", begins a block",
+            CodeBlock::End => summary += ", ends a block",
+            _ => {}
+        }
+        match instruction.symbol() {
+            Symbol::Defines(kind) => summary += &format!(", defines a {kind}"),
+            Symbol::References(kind) => summary += &format!(", refers to a {kind}"),
+            Symbol::NoSymbol => {}
+        }
+        blocks.push(Block::Paragraph(vec![text(summary)]));
+
+        if !instruction.description().is_empty() {
+            blocks.push(Block::Paragraph(vec![text(instruction.description())]));
+        }
+
+        // sized instructions list any bytes not covered by their args as an extra `Unknown` arg
+        let args = instruction.all_args();
+        if !args.is_empty() {
+            let arg_info = instruction.arg_info();
+            blocks.push(Block::Table {
+                headers: vec!["#", "Name", "Type", "Description"],
+                rows: args
+                    .iter()
+                    .enumerate()
+                    .map(|(index, arg)| {
+                        let info = arg_info.get(index).cloned().unwrap_or_default();
+                        let name = if info.name.is_empty() {
+                            vec![]
+                        } else {
+                            vec![code(info.name)]
+                        };
+
+                        vec![
+                            vec![text(index.to_string())],
+                            name,
+                            self.arg_type_spans(arg),
+                            vec![text(info.description)],
+                        ]
+                    })
+                    .collect(),
+            });
+        }
+
+        blocks
+    }
+
+    fn arg_type_spans(&self, arg: &ArgType) -> Vec<Span> {
+        match arg {
+            ArgType::Enum(name) if self.named_value_maps.contains_key(name) => {
+                vec![text("Enum "), link(name, enum_anchor(name))]
+            }
+            ArgType::Enum(name) => vec![text("Enum "), code(name)],
+            ArgType::Unknown(size) => vec![text(format!("Unknown ({size} bytes)"))],
+            other => vec![text(format!("{other:?}"))],
+        }
+    }
+}
+
+fn instruction_anchor(id: u32) -> String {
+    format!("instruction-{id}")
+}
+
+/// Anchors only keep ASCII letters and digits of enum names, so they are valid in both formats.
+/// Other characters are written as `-` followed by their hex code point and another `-`,
+/// so that different names never share an anchor
+fn enum_anchor(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_string()
+            } else {
+                format!("-{:X}-", c as u32)
+            }
+        })
+        .collect();
+    format!("enum-{name}")
+}
+
+fn heading(level: usize, text: Vec<Span>, anchor: impl Into<String>) -> Block {
+    Block::Heading {
+        level,
+        text,
+        anchor: anchor.into(),
+    }
+}
+
+fn text(text: impl Into<String>) -> Span {
+    Span::Text(text.into())
+}
+
+fn code(code: impl Into<String>) -> Span {
+    Span::Code(code.into())
+}
+
+fn link(text: impl Into<String>, anchor: impl Into<String>) -> Span {
+    Span::Link {
+        text: text.into(),
+        anchor: anchor.into(),
+    }
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let spans = |spans: &[Span]| -> String {
+        spans
+            .iter()
+            .map(|span| match span {
+                Span::Text(text) => escape_markdown(text),
+                Span::Code(code) if code.contains('`') => format!("`` {code} ``"),
+                Span::Code(code) => format!("`{code}`"),
+                Span::Link { text, anchor } => format!("[{}](#{anchor})", escape_markdown(text)),
+            })
+            .collect::<String>()
+            // line breaks would end a paragraph or table row
+            .replace('\n', "<br>")
+    };
+
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading {
+                level,
+                text,
+                anchor,
+            } => {
+                out += &format!("<a id=\"{anchor}\"></a>\n\n");
+                out += &format!("{} {}\n\n", "#".repeat(*level), spans(text));
+            }
+            Block::Paragraph(text) => out += &format!("{}\n\n", spans(text)),
+            Block::Table { headers, rows } => {
+                out += &format!("| {} |\n", headers.join(" | "));
+                out += &format!("|{}\n", " --- |".repeat(headers.len()));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|cell| spans(cell)).collect();
+                    out += &format!("| {} |\n", cells.join(" | "));
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn render_html(title: &str, blocks: &[Block]) -> String {
+    let spans = |spans: &[Span]| -> String {
+        spans
+            .iter()
+            .map(|span| match span {
+                Span::Text(text) => escape_html(text).replace('\n', "<br>"),
+                Span::Code(code) => format!("<code>{}</code>", escape_html(code)),
+                Span::Link { text, anchor } => {
+                    format!("<a href=\"#{anchor}\">{}</a>", escape_html(text))
+                }
+            })
+            .collect()
+    };
+
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+        <style>\n\
+        body {{ font-family: sans-serif; max-width: 60em; margin: auto; }}\n\
+        table {{ border-collapse: collapse; }}\n\
+        th, td {{ border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; }}\n\
+        </style>\n</head>\n<body>\n",
+        escape_html(title)
+    );
+
+    for block in blocks {
+        match block {
+            Block::Heading {
+                level,
+                text,
+                anchor,
+            } => {
+                out += &format!("<h{level} id=\"{anchor}\">{}</h{level}>\n", spans(text));
+            }
+            Block::Paragraph(text) => out += &format!("<p>{}</p>\n", spans(text)),
+            Block::Table { headers, rows } => {
+                out += "<table>\n<tr>";
+                for header in headers {
+                    out += &format!("<th>{}</th>", escape_html(header));
+                }
+                out += "</tr>\n";
+                for row in rows {
+                    out += "<tr>";
+                    for cell in row {
+                        out += &format!("<td>{}</td>", spans(cell));
+                    }
+                    out += "</tr>\n";
+                }
+                out += "</table>\n";
+            }
+        }
+    }
+
+    out += "</body>\n</html>\n";
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::DocsFormat;
+    use crate::game_config::test::config;
+
+    #[test]
+    fn markdown_docs() {
+        let docs = config().render_docs("Test", DocsFormat::Markdown);
+
+        assert!(docs.starts_with("<a id=\"top\"></a>\n\n# Test\n\n"));
+        assert!(
+            docs.contains("| 7 | [turn](#instruction-7) | 20 | Turns \\<around\\> \\| back |\n")
+        );
+        assert!(docs.contains("ID 0, 36 bytes, begins a block, defines a state\n"));
+        assert!(docs.contains("| 1 | `to` | Enum [Direction](#enum-Direction) | Side to face |\n"));
+        // the 4 bytes not covered by known args are listed as well
+        assert!(docs.contains("| 0 |  | Number |  |\n| 1 |  | Unknown (4 bytes) |  |\n"));
+        assert!(docs.contains("<a id=\"enum-Direction\"></a>\n\n### `Direction`\n\nUsed by [turn](#instruction-7), [face](#instruction-12)\n\n"));
+        assert!(docs.contains("| 1 | `Right` |\n"));
+        assert!(docs.contains("| 0 | `Tmp` |\n"));
+    }
+
+    #[test]
+    fn distinct_enum_anchors() {
+        let anchors = ["Hit Type", "Hit-Type", "A_B", "A B", "A-20-B"].map(super::enum_anchor);
+
+        assert_eq!(anchors[0], "enum-Hit-20-Type");
+        for (index, anchor) in anchors.iter().enumerate() {
+            assert!(!anchors[index + 1..].contains(anchor));
+        }
+    }
+
+    #[test]
+    fn html_docs() {
+        let docs = config().render_docs("Test & co", DocsFormat::Html);
+
+        assert!(docs.contains("<title>Test &amp; co</title>"));
+        assert!(docs.contains("<h3 id=\"instruction-7\"><code>turn</code></h3>\n"));
+        assert!(docs.contains("<p>Turns &lt;around&gt; | back</p>\n"));
+        assert!(docs.contains("<td>Enum <a href=\"#enum-Direction\">Direction</a></td>"));
+        assert!(docs.ends_with("</body>\n</html>\n"));
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::ops::Range;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +11,20 @@ pub enum BBScriptError {
     ConfigInvalid(String),
     #[error("Config contains one or more duplicate names: {0:?}")]
     ConfigDuplicateName(Vec<String>),
+    #[error("Arg {1} of instruction ID {0} refers to enum `{2}`, which is missing from `named_value_maps`")]
+    ConfigUndefinedEnum(u32, usize, String),
+    #[error("Jump table ID {0} is not an instruction with a `Begin` code block")]
+    ConfigBadJumpTableId(u32),
+    #[error("Instruction ID {0} documents {1} args, but only has {2}")]
+    ConfigExtraArgInfo(u32, usize, usize),
+    #[error("Instruction ID {0} has more than one arg named `{1}`")]
+    ConfigDuplicateArgName(u32, String),
+    #[error("{0} `{1}` can't be read back from readable BBScript")]
+    ConfigUnreadableName(String, String),
+    #[error("Instruction ID {0} is named `{1}`, which is how the unnamed instruction with ID {2} is written")]
+    ConfigAmbiguousName(u32, String, u32),
+    #[error("Overlay adds instruction ID {0} without a size")]
+    OverlayMissingSize(u32),
     #[error("Input `{0}` does not exist or is a directory")]
     BadInputFile(String),
     #[error("Output file `{0}` already exists, specify overwrite with -o flag")]
@@ -18,18 +35,66 @@ pub enum BBScriptError {
     UnknownInstructionID(u32),
     #[error("No variable ID associated with `{0}` in config")]
     NoVariableName(String),
+    #[error("Instruction `{1}` has no arg named `{0}`")]
+    UnknownArgName(String, String),
+    #[error("Arg {0} of instruction `{1}` is given more than once")]
+    DuplicateArg(String, String),
+    #[error("Arg {0} of instruction `{1}` is missing")]
+    MissingArg(String, String),
+    #[error("Unnamed args of instruction `{0}` must come before named args")]
+    PositionalArgAfterNamed(String),
     #[error("No enum associated with index argument {0} in instruction {1}`")]
     NoEnum(usize, u32),
     #[error("Argument tried to access nonexistant enum `{0}`")]
     BadEnumReference(String),
+    #[error("Enum variant `{0}` can't be used by an instruction missing from the config, use its number instead")]
+    UntypedEnumVariant(String),
     #[error("No value associated with variant `{0}` in enum `{1}`")]
     NoAssociatedValue(String, String),
+    /// The index of the referencing instruction is only known when there is no readable script to point at
+    #[error("Undefined {0} `{1}`{}{}", referenced_at(.2), symbols_from_hint(.0))]
+    UndefinedSymbol(crate::game_config::SymbolKind, String, Option<usize>),
+    #[error("Instruction `{0}` at index {1} ends a block that was never begun")]
+    UnmatchedBlockEnd(String, usize),
+    #[error("Block begun by instruction `{0}` at index {1} is never ended")]
+    UnclosedBlock(String, usize),
     #[error(
         "Jump table size of `{0}` is too big! Is the program reading from the correct offset?"
     )]
     IncorrectJumpTableSize(String),
+    #[error("Script needs {0} bytes for the entry counts of its jump tables, but is only {1} bytes long")]
+    TruncatedJumpTableCounts(usize, usize),
     #[error("Got instruction `{0}` mismatched to size {1}. size defined in config is {2}")]
     IncorrectFunctionSize(String, usize, usize),
+    #[error(
+        "Instruction {} at offset {0:#X} needs {2} bytes, but only {3} are available",
+        describe_id(.1)
+    )]
+    TruncatedInstruction(usize, Option<u32>, usize, usize),
+    #[error("Script offsets {0:#X} from the start and {1:#X} from the end don't fit in a file of {2:#X} bytes")]
+    BadScriptOffsets(usize, usize, usize),
+    #[error("Could not decode container file `{0}`: {1}")]
+    ContainerInvalid(String, String),
+    #[error("Failed to export script: {0}")]
+    ExportError(String),
+    #[error("Failed to import script: {0}")]
+    ImportError(String),
+    #[error("{0} is only supported for sized configs")]
+    UnsupportedForUnsized(String),
+    #[error("{0} of {1} files did not round-trip cleanly")]
+    RoundTripFailed(usize, usize),
+    #[error("{0} of {1} files failed to process")]
+    BatchFailed(usize, usize),
+    #[error("Inputs `{0}` and `{1}` would both be written to `{2}`")]
+    DuplicateOutput(String, String, String),
+    #[error("{0} of {1} files are not formatted")]
+    Unformatted(usize, usize),
+    #[error("{0} of {1} files have denied lints")]
+    LintsDenied(usize, usize),
+    #[error("{0}\n{1}")]
+    Spanned(Box<BBScriptError>, SourceSpan),
+    #[error("{} errors found\n\n{}", .0.len(), join_errors(.0))]
+    Multiple(Vec<BBScriptError>),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
     #[error(transparent)]
@@ -37,3 +102,139 @@ pub enum BBScriptError {
     #[error(transparent)]
     FormatError(#[from] std::fmt::Error),
 }
+
+fn describe_id(id: &Option<u32>) -> String {
+    match id {
+        Some(id) => format!("with ID {id} (hex: {id:#X})"),
+        None => "with unreadable ID".into(),
+    }
+}
+
+fn referenced_at(index: &Option<usize>) -> String {
+    match index {
+        Some(index) => format!(" referenced by the instruction at index {index}"),
+        None => String::new(),
+    }
+}
+
+/// States and subroutines are often defined in a common script rather than the one being rebuilt
+fn symbols_from_hint(kind: &crate::game_config::SymbolKind) -> &'static str {
+    match kind {
+        crate::game_config::SymbolKind::Label => "",
+        _ => ", if it's defined in another script such as a common script, pass that script with `--symbols-from`",
+    }
+}
+
+fn join_errors(errors: &[BBScriptError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+impl BBScriptError {
+    /// Combines several errors into one, sorted by their location in the script.
+    /// A single error is returned as is
+    pub fn from_errors(mut errors: Vec<BBScriptError>) -> Self {
+        if errors.len() == 1 {
+            return errors.remove(0);
+        }
+
+        errors.sort_by_key(|e| e.span().map(|span| (span.line, span.column)));
+        BBScriptError::Multiple(errors)
+    }
+
+    /// Attaches a location in a readable script to the error, if the location is known
+    pub fn at(self, source: &str, span: Option<Range<usize>>) -> Self {
+        match span {
+            Some(span) => BBScriptError::Spanned(Box::new(self), SourceSpan::new(source, span)),
+            None => self,
+        }
+    }
+
+    /// Sets the file name shown when displaying the location of the error
+    pub fn with_file(self, file: impl AsRef<str>) -> Self {
+        match self {
+            BBScriptError::Spanned(inner, mut span) => {
+                span.file = Some(file.as_ref().to_string());
+                BBScriptError::Spanned(inner, span)
+            }
+            BBScriptError::PestConsumeError(e) => {
+                BBScriptError::PestConsumeError(Box::new(e.with_path(file.as_ref())))
+            }
+            BBScriptError::Multiple(errors) => BBScriptError::Multiple(
+                errors
+                    .into_iter()
+                    .map(|e| e.with_file(file.as_ref()))
+                    .collect(),
+            ),
+            e => e,
+        }
+    }
+
+    /// Returns the location in a readable script the error was found at, if it is known
+    pub fn span(&self) -> Option<&SourceSpan> {
+        match self {
+            BBScriptError::Spanned(_, span) => Some(span),
+            _ => None,
+        }
+    }
+}
+
+/// Location of an error in a readable script, displayed as a snippet of the offending line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    pub file: Option<String>,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column of the first character, counted in characters
+    pub column: usize,
+    /// Number of characters covered on `source_line`
+    pub len: usize,
+    pub source_line: String,
+}
+
+impl SourceSpan {
+    /// Creates a span from a range of bytes in `source`.
+    /// Spans covering several lines are cut off at the end of the first line
+    pub fn new(source: &str, range: Range<usize>) -> Self {
+        let start = range.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+
+        let source_line = source[line_start..line_end].trim_end_matches('\r');
+        let end = range.end.clamp(start, line_start + source_line.len());
+
+        SourceSpan {
+            file: None,
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            len: source[start..end].chars().count().max(1),
+            source_line: source_line.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        // tabs are kept so the carets line up with the source line
+        let padding: String = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        match &self.file {
+            Some(file) => writeln!(f, "{gutter}--> {file}:{}:{}", self.line, self.column)?,
+            None => writeln!(f, "{gutter}--> {}:{}", self.line, self.column)?,
+        }
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(f, "{gutter} | {padding}{}", "^".repeat(self.len))
+    }
+}
@@ -0,0 +1,275 @@
+use byteorder::ByteOrder;
+use serde::{Deserialize, Serialize};
+
+use crate::error::BBScriptError;
+use crate::game_config::{BBSNumber, CodeBlock, ScriptConfig, SizedString, TaggedValue};
+use crate::parser::{ArgValue, InstructionValue, TextOptions};
+use crate::rebuilder::{check_instruction_symbols, rebuild_from_instructions, ExternalSymbols};
+
+/// Structured formats a parsed script can be exported to and imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ScriptFormat {
+    /// The readable BBScript text format
+    Text,
+    Json,
+    Yaml,
+    Ron,
+}
+
+/// A script in a form suitable for serializing with serde
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedScript {
+    pub instructions: Vec<ExportedInstruction>,
+}
+
+/// An instruction in an [`ExportedScript`].
+///
+/// The `id` is used to identify the instruction when importing, `name` is informational
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedInstruction {
+    pub id: u32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub args: Vec<ExportedArg>,
+}
+
+/// An argument in an [`ExportedInstruction`].
+///
+/// Named variants and variables store both their numeric value and their name from the config,
+/// when importing, the name takes precedence over the value if it is present
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ExportedArg {
+    /// Raw bytes as an uppercase hex string
+    Unknown(String),
+    Number(BBSNumber),
+    String16(String),
+    String32(String),
+    Literal(BBSNumber),
+    Variable {
+        id: BBSNumber,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    BadTag {
+        tag: BBSNumber,
+        value: BBSNumber,
+    },
+    Enum {
+        enum_name: String,
+        value: BBSNumber,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        variant: Option<String>,
+    },
+}
+
+impl ScriptConfig {
+    /// Converts parsed instructions into an [`ExportedScript`], resolving names using this config
+    pub fn export(&self, program: &[InstructionValue]) -> ExportedScript {
+        let instructions = program
+            .iter()
+            .map(|instruction| ExportedInstruction {
+                id: instruction.id,
+                name: instruction.name.clone(),
+                args: instruction
+                    .args
+                    .iter()
+                    .map(|arg| self.export_arg(arg))
+                    .collect(),
+            })
+            .collect();
+
+        ExportedScript { instructions }
+    }
+
+    fn export_arg(&self, arg: &ArgValue) -> ExportedArg {
+        match arg {
+            ArgValue::Unknown(data) => ExportedArg::Unknown(hex::encode_upper(data)),
+            ArgValue::Number(num) => ExportedArg::Number(*num),
+            ArgValue::String16(s) => ExportedArg::String16(s.0.clone()),
+            ArgValue::String32(s) => ExportedArg::String32(s.0.clone()),
+            ArgValue::AccessedValue(TaggedValue::Literal(val)) => ExportedArg::Literal(*val),
+            ArgValue::AccessedValue(TaggedValue::Variable(id)) => ExportedArg::Variable {
+                id: *id,
+                name: self.named_variables.get_by_left(id).cloned(),
+            },
+            ArgValue::AccessedValue(TaggedValue::Improper { tag, value }) => ExportedArg::BadTag {
+                tag: *tag,
+                value: *value,
+            },
+            ArgValue::Enum(enum_name, value) => ExportedArg::Enum {
+                enum_name: enum_name.clone(),
+                value: *value,
+                variant: self
+                    .named_value_maps
+                    .get(enum_name)
+                    .and_then(|map| map.get_by_left(value))
+                    .cloned(),
+            },
+        }
+    }
+
+    /// Converts an [`ExportedScript`] back into instructions, resolving names using this config
+    pub fn import(&self, script: ExportedScript) -> Result<Vec<InstructionValue>, BBScriptError> {
+        script
+            .instructions
+            .into_iter()
+            .map(|instruction| {
+                let info = self.get_by_id(instruction.id);
+
+                let args = instruction
+                    .args
+                    .into_iter()
+                    .map(|arg| self.import_arg(arg))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(InstructionValue {
+                    id: instruction.id,
+                    name: info.as_ref().and_then(|i| i.name()).or(instruction.name),
+                    args,
+                    code_block: info.map_or(CodeBlock::NoBlock, |i| i.block_type()),
+                })
+            })
+            .collect()
+    }
+
+    fn import_arg(&self, arg: ExportedArg) -> Result<ArgValue, BBScriptError> {
+        Ok(match arg {
+            ExportedArg::Unknown(data) => ArgValue::Unknown(
+                hex::decode(&data)
+                    .map_err(|e| BBScriptError::ImportError(format!("`{data}`: {e}")))?
+                    .into(),
+            ),
+            ExportedArg::Number(num) => ArgValue::Number(num),
+            ExportedArg::String16(s) => ArgValue::String16(SizedString(s)),
+            ExportedArg::String32(s) => ArgValue::String32(SizedString(s)),
+            ExportedArg::Literal(val) => ArgValue::AccessedValue(TaggedValue::Literal(val)),
+            ExportedArg::Variable { id, name } => {
+                let id = match name {
+                    Some(name) => self
+                        .get_variable_by_name(name.clone())
+                        .ok_or(BBScriptError::NoVariableName(name))?,
+                    None => id,
+                };
+
+                ArgValue::AccessedValue(TaggedValue::Variable(id))
+            }
+            ExportedArg::BadTag { tag, value } => {
+                ArgValue::AccessedValue(TaggedValue::Improper { tag, value })
+            }
+            ExportedArg::Enum {
+                enum_name,
+                value,
+                variant,
+            } => {
+                let value = match variant {
+                    Some(variant) => self
+                        .get_enum_value(enum_name.clone(), variant.clone())
+                        .ok_or_else(|| {
+                            BBScriptError::NoAssociatedValue(variant, enum_name.clone())
+                        })?,
+                    None => value,
+                };
+
+                ArgValue::Enum(enum_name, value)
+            }
+        })
+    }
+
+    /// Parses a script and writes it in the given format
+    pub fn parse_to_format<B: ByteOrder>(
+        &self,
+        input: impl AsRef<[u8]>,
+        format: ScriptFormat,
+        options: impl Into<TextOptions>,
+    ) -> Result<String, BBScriptError> {
+        let program = self.parse::<B>(input)?;
+
+        self.instructions_to_format(&program, format, options)
+    }
+
+    /// Writes already parsed instructions in the given format, `options` only apply to the text format
+    pub fn instructions_to_format(
+        &self,
+        program: &[InstructionValue],
+        format: ScriptFormat,
+        options: impl Into<TextOptions>,
+    ) -> Result<String, BBScriptError> {
+        let exported = self.export(program);
+
+        match format {
+            ScriptFormat::Text => self.instructions_to_string(program, options),
+            ScriptFormat::Json => serde_json::to_string_pretty(&exported)
+                .map_err(|e| BBScriptError::ExportError(e.to_string())),
+            ScriptFormat::Yaml => serde_yaml::to_string(&exported)
+                .map_err(|e| BBScriptError::ExportError(e.to_string())),
+            ScriptFormat::Ron => {
+                ron::ser::to_string_pretty(&exported, ron::ser::PrettyConfig::default())
+                    .map_err(|e| BBScriptError::ExportError(e.to_string()))
+            }
+        }
+    }
+}
+
+/// Rebuilds a script written in the given format into BBScript usable by games,
+/// allowing references to states and subroutines in `external`
+pub fn rebuild_from_format<B: ByteOrder>(
+    db: &ScriptConfig,
+    script: String,
+    format: ScriptFormat,
+    external: &ExternalSymbols,
+) -> Result<Vec<u8>, BBScriptError> {
+    let exported: ExportedScript =
+        match format {
+            ScriptFormat::Text => {
+                return crate::rebuilder::rebuild_bbscript_with_symbols::<B>(db, script, external)
+            }
+            ScriptFormat::Json => serde_json::from_str(&script)
+                .map_err(|e| BBScriptError::ImportError(e.to_string()))?,
+            ScriptFormat::Yaml => serde_yaml::from_str(&script)
+                .map_err(|e| BBScriptError::ImportError(e.to_string()))?,
+            ScriptFormat::Ron => {
+                ron::from_str(&script).map_err(|e| BBScriptError::ImportError(e.to_string()))?
+            }
+        };
+
+    let program = db.import(exported)?;
+    check_instruction_symbols(db, &program, external)?;
+
+    rebuild_from_instructions::<B>(db, &program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rebuild_from_format, ScriptFormat};
+    use crate::game_config::ScriptConfig;
+    use crate::rebuilder::{rebuild_bbscript, ExternalSymbols};
+    use byteorder::LittleEndian;
+
+    #[test]
+    fn structured_round_trip() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+        let script =
+            "beginState: s32'StateA'\n  spriteTimeVariable: s32'spr\\'s', Mem(Tmp)\nendState:";
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap();
+
+        for format in [ScriptFormat::Json, ScriptFormat::Yaml, ScriptFormat::Ron] {
+            let exported = config
+                .parse_to_format::<LittleEndian>(&bytes, format, 12)
+                .unwrap();
+            assert!(exported.contains("Tmp"));
+
+            let rebuilt = rebuild_from_format::<LittleEndian>(
+                &config,
+                exported,
+                format,
+                &ExternalSymbols::default(),
+            )
+            .unwrap();
+            assert_eq!(rebuilt, bytes);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::iter::Peekable;
+use std::ops::Range;
+
+use crate::error::BBScriptError;
+use crate::game_config::ScriptConfig;
+use crate::parser::ScriptWriter;
+use crate::rebuilder::{comment_ranges, find_instruction_info, parse_program};
+
+impl ScriptConfig {
+    /// Rewrites a readable script in the layout produced by [`ScriptConfig::parse_to_string`],
+    /// keeping its comments, and its args with the names they were given.
+    ///
+    /// Comments on their own line stay on their own line before the next instruction,
+    /// comments between args stay before the arg that follows them, and comments after an instruction
+    /// stay at the end of its line
+    pub fn format_script(
+        &self,
+        script: &str,
+        indent_limit: usize,
+    ) -> Result<String, BBScriptError> {
+        let program = parse_program(script)?;
+
+        // the config is only needed to know which instructions begin and end blocks
+        let mut errors = Vec::new();
+        let mut code_blocks = Vec::new();
+        for function in &program {
+            match find_instruction_info(self, function, script) {
+                Ok(info) => code_blocks.push(info.block_type()),
+                Err(e) => errors.push(e),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(BBScriptError::from_errors(errors));
+        }
+
+        let mut writer = ScriptWriter::new(indent_limit);
+        let mut comments = comment_ranges(script).into_iter().peekable();
+
+        for (function, code_block) in program.iter().zip(code_blocks) {
+            let span = function
+                .span
+                .clone()
+                .expect("functions parsed from a script have a span");
+
+            for line in comment_lines(script, &mut comments, span.start) {
+                writer.write_line(&line);
+            }
+
+            let mut arg_comments = vec![Vec::new(); function.args.len()];
+            let mut trailing = Vec::new();
+            while let Some(comment) = comments.next_if(|comment| comment.start < span.end) {
+                match function
+                    .arg_spans
+                    .iter()
+                    .position(|arg| arg.end > comment.start)
+                {
+                    Some(index) => arg_comments[index].push(&script[comment]),
+                    None => trailing.push(&script[comment]),
+                }
+            }
+            let line_end = script[span.end..]
+                .find(['\n', '\r'])
+                .map_or(script.len(), |i| span.end + i);
+            while let Some(comment) = comments.next_if(|comment| comment.start < line_end) {
+                trailing.push(&script[comment]);
+            }
+
+            let args: Vec<String> = function
+                .args
+                .iter()
+                .zip(&function.arg_names)
+                .zip(arg_comments)
+                .map(|((value, name), comments)| {
+                    let arg = match name {
+                        Some(name) => format!("{name}={value}"),
+                        None => value.to_string(),
+                    };
+                    comments
+                        .into_iter()
+                        .map(str::to_string)
+                        .chain([arg])
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+
+            // instructions are written with a space after the colon even without args
+            let mut line = format!("{}: {}", function.name, args.join(", "));
+            if !trailing.is_empty() {
+                if !line.ends_with(' ') {
+                    line.push(' ');
+                }
+                line += &trailing.join(" ");
+            }
+
+            writer.write_instruction(&line, code_block);
+        }
+
+        for line in comment_lines(script, &mut comments, script.len()) {
+            writer.write_line(&line);
+        }
+
+        Ok(writer.finish())
+    }
+}
+
+/// Takes the comments starting before `end`, joining comments that were on the same line
+fn comment_lines<I>(script: &str, comments: &mut Peekable<I>, end: usize) -> Vec<String>
+where
+    I: Iterator<Item = Range<usize>>,
+{
+    let mut lines: Vec<String> = Vec::new();
+    let mut previous_end = None;
+
+    while let Some(comment) = comments.next_if(|comment| comment.start < end) {
+        let same_line = previous_end
+            .is_some_and(|previous_end| !script[previous_end..comment.start].contains('\n'));
+
+        match lines.last_mut() {
+            Some(line) if same_line => {
+                line.push(' ');
+                line.push_str(&script[comment.clone()]);
+            }
+            _ => lines.push(script[comment.clone()].to_string()),
+        }
+        previous_end = Some(comment.end);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::LittleEndian;
+
+    use crate::SupportedGame;
+
+    #[test]
+    fn format_scripts() {
+        let config = SupportedGame::Ggst.into_config();
+        let script = "
+// the first state
+beginState:s32'A' // trailing
+      sprite:  s32'spr' ,/* frames */ duration = +3
+/* spans
+   lines */ /* twice */
+  sprite: s32'it\\'s',   duration=3,
+endState:
+beginState: s32'B'
+endState:   // end
+
+// at the end
+";
+        let formatted = config.format_script(script, 12).unwrap();
+
+        assert_eq!(
+            formatted,
+            "// the first state\n\
+            beginState: s32'A' // trailing\n  \
+            sprite: s32'spr', /* frames */ duration=3\n  \
+            /* spans\n   lines */ /* twice */\n  \
+            sprite: s32'it\\'s', duration=3\n  \
+            endState: \n\n\
+            beginState: s32'B'\n  \
+            endState: // end\n\n\
+            // at the end\n"
+        );
+        // formatting is idempotent and doesn't change the rebuilt script
+        assert_eq!(config.format_script(&formatted, 12).unwrap(), formatted);
+        assert_eq!(
+            crate::rebuild_bbscript::<LittleEndian>(&config, formatted).unwrap(),
+            crate::rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap()
+        );
+    }
+
+    #[test]
+    fn matches_parsed_layout() {
+        let config = SupportedGame::Ggst.into_config();
+        let script =
+            "beginState: s32'A'\n  sprite: s32'spr', 3\nendState:\nbeginState: s32'B'\nendState:";
+        let bytes = crate::rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap();
+        let parsed = config.parse_to_string::<LittleEndian>(&bytes, 1).unwrap();
+
+        assert_eq!(config.format_script(script, 1).unwrap(), parsed);
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{ScriptBlock, ScriptTree};
+use crate::error::BBScriptError;
+use crate::game_config::{BBSNumber, ScriptConfig, Symbol, SymbolKind};
+use crate::parser::ArgValue;
+use crate::vm::StateVm;
+
+/// Format to write frame data in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FrameDataFormat {
+    /// One row per state, with a column for each value instruction
+    Csv,
+    Json,
+}
+
+/// Names of the instructions used to find the frame data of a state, read from a RON file
+/// such as `(hit_active: ["hit"], values: ["attackLevel", "damage"])`.
+///
+/// Fields left out of the file keep their defaults, which are the instruction names in ggst.ron
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrameDataMarkers {
+    /// Instructions that start the active frames of an attack
+    pub hit_active: Vec<String>,
+    /// Instructions that start the recovery frames of an attack
+    pub recovery: Vec<String>,
+    /// Instructions whose first number is collected, such as the attack level or amount of blockstun
+    pub values: Vec<String>,
+}
+
+impl Default for FrameDataMarkers {
+    fn default() -> Self {
+        Self {
+            hit_active: vec!["hit".into()],
+            recovery: vec!["recoveryState".into()],
+            values: vec![
+                "attackLevel".into(),
+                "damage".into(),
+                "hitstunAmount".into(),
+                "blockstunAmount".into(),
+                "counterHitstunAmount".into(),
+            ],
+        }
+    }
+}
+
+impl FrameDataMarkers {
+    pub fn new<T: Read>(markers: T) -> Result<Self, BBScriptError> {
+        ron::de::from_reader(markers).map_err(|e| BBScriptError::ConfigInvalid(e.to_string()))
+    }
+
+    pub fn load<T: AsRef<Path>>(markers_path: T) -> Result<Self, BBScriptError> {
+        let markers_file = File::open(&markers_path).map_err(|e| {
+            BBScriptError::ConfigOpenError(
+                format!("{}", markers_path.as_ref().display()),
+                e.to_string(),
+            )
+        })?;
+
+        Self::new(markers_file)
+    }
+}
+
+/// Frame data of a single state, in frames counted from 1
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StateFrameData {
+    pub state: String,
+    /// Frame the first active frame is on, `None` if the state has no active frames
+    pub startup: Option<usize>,
+    /// Frames from the first active frame until recovery starts
+    pub active: Option<usize>,
+    /// Frames from the start of recovery until the end of the state
+    pub recovery: Option<usize>,
+    /// Frames the state runs for before it ends
+    pub total: usize,
+    /// Values of each value instruction in the state, in the order they appear
+    pub values: BTreeMap<String, Vec<BBSNumber>>,
+}
+
+impl ScriptConfig {
+    /// Finds the frame data of every state in a parsed script by running it with a [`StateVm`].
+    ///
+    /// Instructions after a sprite run once its duration has passed, so a marker starts the active or recovery
+    /// frames on the frame after the sprites before it. Only the branches taken with every variable at `0`
+    /// are counted, and the bodies of blocks the VM doesn't model are skipped.
+    ///
+    /// Every top level block is taken to be a state unless its begin instruction is annotated as defining a
+    /// subroutine, so configs without symbol annotations report their subroutines as well
+    pub fn frame_data(&self, tree: &ScriptTree, markers: &FrameDataMarkers) -> Vec<StateFrameData> {
+        tree.states()
+            .filter(|state| {
+                self.get_by_id(state.begin.id).map(|info| info.symbol())
+                    != Some(Symbol::Defines(SymbolKind::Subroutine))
+            })
+            .map(|state| self.state_frame_data(state, markers))
+            .collect()
+    }
+
+    fn state_frame_data(&self, state: &ScriptBlock, markers: &FrameDataMarkers) -> StateFrameData {
+        let mut data = StateFrameData {
+            state: state.name().unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+        let mut recovery_start = None;
+
+        let mut vm = StateVm::new(self, state);
+        while let Some(frame) = vm.step() {
+            for event in &frame.events {
+                let Some(name) = &event.name else {
+                    continue;
+                };
+
+                if markers.hit_active.contains(name) {
+                    data.startup.get_or_insert(frame.number);
+                } else if markers.recovery.contains(name) && data.startup.is_some() {
+                    recovery_start.get_or_insert(frame.number);
+                }
+
+                if markers.values.contains(name) {
+                    let value = event.args.iter().find_map(|arg| match arg {
+                        ArgValue::Number(value) | ArgValue::Enum(_, value) => Some(*value),
+                        _ => None,
+                    });
+                    if let Some(value) = value {
+                        data.values.entry(name.clone()).or_default().push(value);
+                    }
+                }
+            }
+
+            if frame.sprite.is_some() {
+                data.total = frame.number + vm.skip_sprite();
+            }
+        }
+
+        finish_state(data, recovery_start)
+    }
+}
+
+/// Works out the active and recovery frames once the total frames of the state are known
+fn finish_state(mut state: StateFrameData, recovery_start: Option<usize>) -> StateFrameData {
+    if let Some(startup) = state.startup {
+        let recovery_start = recovery_start.unwrap_or(state.total + 1);
+
+        state.active = Some(recovery_start.saturating_sub(startup));
+        state.recovery = Some((state.total + 1).saturating_sub(recovery_start));
+    }
+
+    state
+}
+
+/// Writes frame data in the given format. Value columns of CSV follow the order of `markers.values`,
+/// with several values of the same instruction separated by spaces
+pub fn frame_data_to_string(
+    data: &[StateFrameData],
+    markers: &FrameDataMarkers,
+    format: FrameDataFormat,
+) -> Result<String, BBScriptError> {
+    match format {
+        FrameDataFormat::Json => serde_json::to_string_pretty(data)
+            .map_err(|e| BBScriptError::ExportError(e.to_string())),
+        FrameDataFormat::Csv => {
+            let mut out = String::from("state,startup,active,recovery,total");
+            for name in &markers.values {
+                write!(out, ",{}", csv_field(name))?;
+            }
+            out.push('\n');
+
+            let optional =
+                |frames: Option<usize>| frames.map(|f| f.to_string()).unwrap_or_default();
+            for state in data {
+                write!(
+                    out,
+                    "{},{},{},{},{}",
+                    csv_field(&state.state),
+                    optional(state.startup),
+                    optional(state.active),
+                    optional(state.recovery),
+                    state.total
+                )?;
+                for name in &markers.values {
+                    let values = state
+                        .values
+                        .get(name)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                    write!(out, ",{}", values.join(" "))?;
+                }
+                out.push('\n');
+            }
+
+            Ok(out)
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a character that would otherwise end it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::LittleEndian;
+
+    use super::{frame_data_to_string, FrameDataFormat, FrameDataMarkers};
+    use crate::game_config::{InstructionInfo, Symbol};
+    use crate::rebuilder::rebuild_bbscript;
+    use crate::SupportedGame;
+
+    const SCRIPT: &str = "beginState: s32'5P'
+  attackLevel: 0
+  damage: 26, 0
+  hitstunAmount: 12
+  blockstunAmount: 9
+  sprite: s32'a', 3
+  sprite: s32'b', 1
+  if: Mem(6)
+    sprite: s32'z', 9
+  endIf:
+  hit:
+  sprite: s32'c', 2
+  recoveryState:
+  sprite: s32'd', 4
+endState:
+beginSubroutine: s32'cmn'
+  hit:
+  sprite: s32'x', 5
+endSubroutine:
+beginState: s32'Idle'
+  sprite: s32'e', 6
+  attackLevel: 1
+  attackLevel: 2
+endState:
+";
+
+    #[test]
+    fn find_frame_data() {
+        let config = SupportedGame::Ggst.into_config();
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, SCRIPT.into()).unwrap();
+        let tree = config.parse_tree::<LittleEndian>(bytes).unwrap();
+
+        let markers = FrameDataMarkers::default();
+        let data = config.frame_data(&tree, &markers);
+
+        // subroutines aren't states, and the sprite in the `if` isn't shown
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].state, "5P");
+        assert_eq!(
+            (
+                data[0].startup,
+                data[0].active,
+                data[0].recovery,
+                data[0].total
+            ),
+            (Some(5), Some(2), Some(4), 10)
+        );
+        assert_eq!(
+            (
+                data[1].startup,
+                data[1].active,
+                data[1].recovery,
+                data[1].total
+            ),
+            (None, None, None, 6)
+        );
+
+        assert_eq!(
+            frame_data_to_string(&data, &markers, FrameDataFormat::Csv).unwrap(),
+            "state,startup,active,recovery,total,attackLevel,damage,hitstunAmount,blockstunAmount,counterHitstunAmount\n\
+             5P,5,2,4,10,0,26,12,9,\n\
+             Idle,,,,6,1 2,,,,\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(
+            &frame_data_to_string(&data, &markers, FrameDataFormat::Json).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json[0]["startup"], 5);
+        assert_eq!(json[0]["values"]["blockstunAmount"][0], 9);
+        assert!(json[1]["active"].is_null());
+    }
+
+    #[test]
+    fn frame_data_without_symbols() {
+        let mut config = SupportedGame::Ggst.into_config();
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, SCRIPT.into()).unwrap();
+        if let InstructionInfo::Sized(instructions) = &mut config.instructions {
+            for instruction in instructions.values_mut() {
+                instruction.symbol = Symbol::NoSymbol;
+            }
+        }
+        let tree = config.parse_tree::<LittleEndian>(bytes).unwrap();
+
+        // without annotations the subroutine can't be told apart from a state
+        let data = config.frame_data(&tree, &FrameDataMarkers::default());
+        let states: Vec<_> = data.iter().map(|state| state.state.as_str()).collect();
+        assert_eq!(states, ["5P", "cmn", "Idle"]);
+        assert_eq!(data[0].startup, Some(5));
+    }
+
+    #[test]
+    fn markers_from_file() {
+        let markers =
+            FrameDataMarkers::new("(hit_active: [\"hit\", \"attackOn\"])".as_bytes()).unwrap();
+        assert_eq!(markers.hit_active, ["hit", "attackOn"]);
+        assert_eq!(markers.recovery, ["recoveryState"]);
+        assert!(FrameDataMarkers::new("(active: [])".as_bytes()).is_err());
+    }
+}
@@ -47,7 +47,7 @@ pub enum ArgType {
     /// The first `i32` is the tag, which is typically `0` for a literal value, and `2` for a variable ID
     ///
     /// `AccessedValue`s are treated specially, the value
-    /// they contain will be translated to a corresponding name using the `variable_config` field in the [`GameDB`]
+    /// they contain will be translated to a corresponding name using the `named_variables` field in the [`ScriptConfig`]
     AccessedValue,
 }
 
@@ -70,11 +70,23 @@ impl ArgType {
     }
 }
 
+/// Documentation for the arg of an instruction at the same index in its `args`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArgInfo {
+    /// Name used for the arg when it is written as `name=value`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaggedValue {
     Literal(BBSNumber),
     Variable(BBSNumber),
-    /// A tagged value whos tag does not match either specified value in the [`GameDB`]
+    /// A tagged value whos tag does not match either specified value in the [`ScriptConfig`]
     Improper {
         tag: BBSNumber,
         value: BBSNumber,
@@ -121,7 +133,10 @@ pub trait Instruction {
     fn name(&self) -> Option<String>;
     fn size(&self) -> Option<usize>;
     fn block_type(&self) -> CodeBlock;
+    fn symbol(&self) -> Symbol;
     fn args(&self) -> &[ArgType];
+    fn arg_info(&self) -> &[ArgInfo];
+    fn description(&self) -> &str;
 }
 
 impl Instruction for SizedInstruction {
@@ -141,9 +156,21 @@ impl Instruction for SizedInstruction {
         self.code_block
     }
 
+    fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
     fn args(&self) -> &[ArgType] {
         self.args.as_slice()
     }
+
+    fn arg_info(&self) -> &[ArgInfo] {
+        self.arg_info.as_slice()
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
 }
 
 impl Instruction for UnsizedInstruction {
@@ -163,12 +190,24 @@ impl Instruction for UnsizedInstruction {
         self.code_block
     }
 
+    fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
     fn args(&self) -> &[ArgType] {
         self.args.as_slice()
     }
+
+    fn arg_info(&self) -> &[ArgInfo] {
+        self.arg_info.as_slice()
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptConfig {
     pub jump_table_ids: Vec<u32>,
     /// The value used for identifying [`TaggedValue::Literal`]s in the scripts
@@ -176,8 +215,9 @@ pub struct ScriptConfig {
     /// The value used for identifying [`TaggedValue::Variable`]s in the scripts
     pub variable_tag: BBSNumber,
     /// A map that allows associating names with specific values of a [`TaggedValue::Variable`]
+    #[serde(serialize_with = "ordered_bimap")]
     pub named_variables: BiMap<BBSNumber, String>,
-    /// A map of [`BBSArgType::Enum`] maps for naming specific values
+    /// A map of [`ArgType::Enum`] maps for naming specific values
     #[serde(serialize_with = "ordered_enums")]
     pub named_value_maps: HashMap<String, BiMap<BBSNumber, String>>,
     pub(crate) instructions: InstructionInfo,
@@ -186,16 +226,35 @@ pub struct ScriptConfig {
 impl ScriptConfig {
     #[inline]
     pub fn new<T: Read>(config: T) -> Result<Self, BBScriptError> {
-        use std::collections::HashSet;
         let config: Self =
             de::from_reader(config).map_err(|e| BBScriptError::ConfigInvalid(e.to_string()))?;
 
         // initial sanity checks for the config
 
-        // ensure config contains no duplicate names
-        // otherwise it will return an error with the name
+        config.check_duplicate_names()?;
+
+        // warn if args greater than specified size
+        if let InstructionInfo::Sized(ref map) = config.instructions {
+            map.iter().for_each(|(id, instruction)| {
+                let arg_list_size = instruction.args.iter().fold(0, |size, arg| size + arg.size());
+
+                if instruction.size < arg_list_size {
+                    let min_size = arg_list_size + 4;
+                    log::warn!("Instruction ID {id} size too small for known args! Size should be at least {min_size} to support arguments");
+                }
+            })
+        }
+
+        Ok(config)
+    }
+
+    /// Ensures the config contains no duplicate names,
+    /// otherwise it will return an error with the names
+    pub(crate) fn check_duplicate_names(&self) -> Result<(), BBScriptError> {
+        use std::collections::HashSet;
+
         let mut set = HashSet::new();
-        let duplicate_names: Vec<String> = config
+        let duplicate_names: Vec<String> = self
             .instructions
             .iter_generic()
             .filter_map(|(_, i)| {
@@ -213,19 +272,7 @@ impl ScriptConfig {
             return Err(BBScriptError::ConfigDuplicateName(duplicate_names));
         }
 
-        // warn if args greater than specified size
-        if let InstructionInfo::Sized(ref map) = config.instructions {
-            map.iter().for_each(|(id, instruction)| {
-                let arg_list_size = instruction.args.iter().fold(0, |size, arg| size + arg.size());
-
-                if instruction.size < arg_list_size {
-                    let min_size = arg_list_size + 4;
-                    log::warn!("Instruction ID {id} size too small for known args! Size should be at least {min_size} to support arguments");
-                }
-            })
-        }
-
-        Ok(config)
+        Ok(())
     }
 
     pub fn load<T: AsRef<Path>>(config_path: T) -> Result<Self, BBScriptError> {
@@ -276,17 +323,158 @@ impl ScriptConfig {
         self.named_variables.get_by_right(&variable_name).copied()
     }
 
+    /// Returns the documentation of the args of an instruction, which may not cover every arg
+    pub fn arg_info(&self, id: u32) -> &[ArgInfo] {
+        match self.instructions {
+            InstructionInfo::Sized(ref map) => map.get(&id).map(|i| i.arg_info.as_slice()),
+            InstructionInfo::Unsized(ref map) => map.get(&id).map(|i| i.arg_info.as_slice()),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Returns the description of an instruction, which is empty if it has none
+    pub fn description(&self, id: u32) -> &str {
+        match self.instructions {
+            InstructionInfo::Sized(ref map) => map.get(&id).map(|i| i.description.as_str()),
+            InstructionInfo::Unsized(ref map) => map.get(&id).map(|i| i.description.as_str()),
+        }
+        .unwrap_or_default()
+    }
+
     pub fn is_unsized(&self) -> bool {
         matches!(self.instructions, InstructionInfo::Unsized(_))
     }
 
-    /// Returns `true` if this instruction ID is one that should be included in the jump table
-    pub fn is_jump_entry_id(&self, id: u32) -> bool {
-        self.jump_table_ids.contains(&id)
+    /// Checks the config for problems that would otherwise only show up when parsing or rebuilding scripts,
+    /// returning every problem found.
+    ///
+    /// Enum args must refer to an existing enum, jump table IDs must be instructions that begin a block,
+    /// arg names must be unique within an instruction, and the names of instructions, args, enum variants
+    /// and variables must be read back unchanged from readable BBScript.
+    /// Known args that don't fit in the size of a sized instruction are logged as a warning
+    pub fn check(&self) -> Result<(), BBScriptError> {
+        use crate::rebuilder::{
+            is_readable_arg_name, is_readable_instruction_name, is_readable_variable_name,
+            is_readable_variant_name,
+        };
+
+        let mut errors = Vec::new();
+
+        for id in &self.jump_table_ids {
+            if !self
+                .get_by_id(*id)
+                .is_some_and(|i| i.block_type() == CodeBlock::Begin)
+            {
+                errors.push(BBScriptError::ConfigBadJumpTableId(*id));
+            }
+        }
+
+        let instructions: std::collections::BTreeMap<_, _> =
+            self.instructions.iter_generic().collect();
+
+        for (id, instruction) in instructions {
+            for (index, arg) in instruction.args().iter().enumerate() {
+                if let ArgType::Enum(name) = arg {
+                    if !self.named_value_maps.contains_key(name) {
+                        errors.push(BBScriptError::ConfigUndefinedEnum(id, index, name.clone()));
+                    }
+                }
+            }
+
+            // bytes left over after the known args are read as one more `Unknown` arg
+            let arg_count = instruction.args().len()
+                + match instruction.size() {
+                    Some(size) => {
+                        let args_size: usize = instruction.args().iter().map(ArgType::size).sum();
+                        let available = size.saturating_sub(4);
+
+                        // embedded configs have instructions like this that still parse fine,
+                        // so it's only a warning the same as when loading the config
+                        if args_size > available {
+                            log::warn!("Args of instruction ID {id} take {args_size} bytes, but its size only leaves room for {available}");
+                        }
+
+                        usize::from(args_size < available)
+                    }
+                    None => 1,
+                };
+
+            if instruction.arg_info().len() > arg_count {
+                errors.push(BBScriptError::ConfigExtraArgInfo(
+                    id,
+                    instruction.arg_info().len(),
+                    arg_count,
+                ));
+            }
+
+            let mut arg_names = std::collections::HashSet::new();
+            for arg_name in instruction.arg_info().iter().map(|info| &info.name) {
+                if arg_name.is_empty() {
+                    continue;
+                }
+
+                if !is_readable_arg_name(arg_name) {
+                    errors.push(BBScriptError::ConfigUnreadableName(
+                        format!("Arg name of instruction ID {id}"),
+                        arg_name.clone(),
+                    ));
+                } else if !arg_names.insert(arg_name) {
+                    errors.push(BBScriptError::ConfigDuplicateArgName(id, arg_name.clone()));
+                }
+            }
+
+            if let Some(name) = instruction.name() {
+                if !is_readable_instruction_name(&name) {
+                    errors.push(BBScriptError::ConfigUnreadableName(
+                        "Instruction name".into(),
+                        name,
+                    ));
+                } else if let Some(other) = name
+                    .strip_prefix("Unknown")
+                    .and_then(|other| other.parse().ok())
+                    .filter(|other| *other != id)
+                {
+                    errors.push(BBScriptError::ConfigAmbiguousName(id, name, other));
+                }
+            }
+        }
+
+        let enums: std::collections::BTreeMap<_, _> = self.named_value_maps.iter().collect();
+
+        for (enum_name, variants) in enums {
+            for (_, variant) in bimap::BiBTreeMap::from_iter(variants) {
+                if !is_readable_variant_name(variant) {
+                    errors.push(BBScriptError::ConfigUnreadableName(
+                        format!("Variant of enum `{enum_name}`"),
+                        variant.to_string(),
+                    ));
+                }
+            }
+        }
+
+        for (_, variable) in bimap::BiBTreeMap::from_iter(&self.named_variables) {
+            if !is_readable_variable_name(variable) {
+                errors.push(BBScriptError::ConfigUnreadableName(
+                    "Variable name".into(),
+                    variable.to_string(),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BBScriptError::from_errors(errors))
+        }
+    }
+
+    /// Returns the position in `jump_table_ids` of the jump table this instruction ID should be included in, if any
+    pub fn jump_table_index(&self, id: u32) -> Option<usize> {
+        self.jump_table_ids.iter().position(|x| *x == id)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SizedInstruction {
     pub size: usize,
@@ -294,14 +482,39 @@ pub struct SizedInstruction {
     #[serde(skip_serializing_if = "String::is_empty")]
     pub name: String,
     #[serde(default)]
+    #[serde(skip_serializing_if = "CodeBlock::is_none")]
     pub code_block: CodeBlock,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Symbol::is_none")]
+    pub symbol: Symbol,
     args: SmallVec<[ArgType; 16]>,
     #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arg_info: Vec<ArgInfo>,
+    #[serde(default)]
     #[serde(skip_serializing_if = "String::is_empty")]
     pub description: String,
 }
 
 impl SizedInstruction {
+    /// Creates an unnamed instruction of the given size with no known args
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            name: String::new(),
+            code_block: CodeBlock::NoBlock,
+            symbol: Symbol::NoSymbol,
+            args: SmallVec::new(),
+            arg_info: Vec::new(),
+            description: String::new(),
+        }
+    }
+
+    /// Replaces the args of the instruction, any bytes they don't cover are still read as an `Unknown` arg
+    pub fn set_args(&mut self, args: SmallVec<[ArgType; 16]>) {
+        self.args = args;
+    }
+
     pub fn args(&self) -> SmallVec<[ArgType; 16]> {
         const INSTRUCTION_SIZE: usize = 0x4;
         let known_args_size: usize = self.args.iter().map(|a| a.size()).sum();
@@ -321,40 +534,46 @@ impl SizedInstruction {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsizedInstruction {
     #[serde(default)]
     #[serde(skip_serializing_if = "String::is_empty")]
     pub name: String,
     #[serde(default)]
+    #[serde(skip_serializing_if = "CodeBlock::is_none")]
     pub code_block: CodeBlock,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Symbol::is_none")]
+    pub symbol: Symbol,
     pub args: SmallVec<[ArgType; 16]>,
     #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arg_info: Vec<ArgInfo>,
+    #[serde(default)]
     #[serde(skip_serializing_if = "String::is_empty")]
     pub description: String,
 }
 
 impl UnsizedInstruction {
     pub fn new() -> Self {
-        Self {
-            name: String::new(),
-            code_block: CodeBlock::NoBlock,
-            args: SmallVec::new(),
-            description: String::new(),
-        }
+        Self::default()
     }
 
     pub fn from_parsed(args: Vec<ArgType>) -> Self {
         Self {
             name: String::new(),
             code_block: CodeBlock::NoBlock,
+            symbol: Symbol::NoSymbol,
             args: args.into(),
+            arg_info: Vec::new(),
             description: String::new(),
         }
     }
 
-    pub fn args_with_known_size(&self, dynamic_size: usize) -> SmallVec<[ArgType; 16]> {
+    /// Returns the args of the instruction, with any bytes not covered by the config as an `Unknown` arg.
+    /// Returns `None` if the args in the config are larger than `dynamic_size`
+    pub fn args_with_known_size(&self, dynamic_size: usize) -> Option<SmallVec<[ArgType; 16]>> {
         const INSTRUCTION_SIZE: usize = 0x8;
         let known_args_size: usize = self.args.iter().map(|a| a.size()).sum();
 
@@ -362,21 +581,20 @@ impl UnsizedInstruction {
 
         let mut args = self.args.clone();
 
-        if known_args_size > (dynamic_size - INSTRUCTION_SIZE) {
-            panic!("dynamic argument size smaller than argument size in config!")
-        }
+        // size has an extra 8 bytes because of the ID and size being u32s
+        let left_over = dynamic_size
+            .checked_sub(INSTRUCTION_SIZE)?
+            .checked_sub(known_args_size)?;
 
-        if known_args_size != (dynamic_size - INSTRUCTION_SIZE) {
-            // size typically has an extra 4 bytes because of the ID being a u32
-            let left_over = (dynamic_size.saturating_sub(INSTRUCTION_SIZE)) - known_args_size;
+        if left_over != 0 {
             args.push(ArgType::Unknown(left_over))
         }
 
-        args
+        Some(args)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum GenericInstruction {
     Sized(u32, SizedInstruction),
     Unsized(u32, UnsizedInstruction),
@@ -389,6 +607,21 @@ impl GenericInstruction {
             GenericInstruction::Unsized(id, _) => *id,
         }
     }
+
+    /// The instruction's name, or `Unknown` followed by its ID if it is unnamed
+    pub fn display_name(&self) -> String {
+        self.name()
+            .unwrap_or_else(|| format!("Unknown{}", self.id()))
+    }
+
+    /// Returns the args of the instruction, for sized instructions followed by an `Unknown` arg
+    /// covering any bytes left over after the known args
+    pub fn all_args(&self) -> SmallVec<[ArgType; 16]> {
+        match self {
+            GenericInstruction::Sized(_, i) => i.args(),
+            GenericInstruction::Unsized(_, i) => i.args.clone(),
+        }
+    }
 }
 
 impl std::ops::Deref for GenericInstruction {
@@ -522,6 +755,14 @@ where
     ordered.serialize(serializer)
 }
 
+/// Serialize bimap as BiBTreeMap for ordered keys
+fn ordered_bimap<S>(value: &BiMap<BBSNumber, String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    bimap::BiBTreeMap::from_iter(value).serialize(serializer)
+}
+
 /// Serialize hashmap as BTreeMap for ordered keys
 fn ordered_map<S, K, V>(value: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -534,10 +775,53 @@ where
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
+    use crate::error::BBScriptError;
     use crate::game_config::ScriptConfig;
     use walkdir::WalkDir;
 
+    /// Small config with an instruction of each kind, shared by tests that work on readable scripts
+    pub(crate) fn config() -> ScriptConfig {
+        ScriptConfig::new(
+            r#"(
+                jump_table_ids: [0, 2],
+                literal_tag: 0,
+                variable_tag: 2,
+                named_variables: { 0: "Tmp", 47: "Hits" },
+                named_value_maps: { "Direction": { 0: "Left", 1: "Right" } },
+                instructions: Sized({
+                    0: (size: 36, name: "beginState", codeBlock: Begin, symbol: Defines(State), args: [String32]),
+                    1: (size: 4, name: "endState", codeBlock: End, args: []),
+                    2: (size: 36, name: "beginSubroutine", codeBlock: Begin, symbol: Defines(Subroutine), args: [String32]),
+                    3: (size: 4, name: "endSubroutine", codeBlock: End, args: []),
+                    4: (size: 36, name: "callSubroutine", symbol: References(Subroutine), args: [String32]),
+                    5: (size: 20, name: "label", symbol: Defines(Label), args: [String16]),
+                    6: (size: 20, name: "gotoLabel", symbol: References(Label), args: [String16]),
+                    7: (
+                        size: 20,
+                        name: "turn",
+                        args: [Number, Enum("Direction"), AccessedValue],
+                        argInfo: [(name: "speed"), (name: "to", description: "Side to face")],
+                        description: "Turns <around> | back",
+                    ),
+                    8: (size: 4, name: "if", codeBlock: Begin, args: []),
+                    9: (size: 4, name: "endIf", codeBlock: End, args: []),
+                    10: (size: 12, name: "store", args: [AccessedValue], argInfo: [(name: "from")]),
+                    11: (size: 12, name: "pad", args: [Number]),
+                    12: (
+                        size: 12,
+                        name: "face",
+                        args: [Enum("Direction"), Enum("Direction")],
+                        argInfo: [(name: "from", description: "Where the character faced")],
+                        description: "Faces a side\nafter turning",
+                    ),
+                }),
+            )"#
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
     #[test]
     fn deserialize_configs() {
         for entry in WalkDir::new("./static_db")
@@ -556,6 +840,72 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn check_config() {
+        let config = ScriptConfig::new(
+            r#"(
+                jump_table_ids: [0, 1],
+                literal_tag: 0,
+                variable_tag: 2,
+                named_variables: { 0: "Tmp", 1: "1stHit", 2: "Pos(X)" },
+                named_value_maps: {
+                    "Direction": { 0: "Left", 1: " Right", 2: "Up/Down" },
+                },
+                instructions: Sized({
+                    0: (size: 36, name: "beginState", codeBlock: Begin, args: [String32]),
+                    1: (size: 4, name: "endState", codeBlock: End, args: []),
+                    2: (size: 8, name: "turn", args: [Enum("Direction")]),
+                    3: (size: 8, name: "face", args: [Enum("Facing")]),
+                    4: (size: 8, name: "wait:", args: [Number, Number]),
+                    5: (size: 4, name: "Unknown6", args: []),
+                    6: (size: 4, args: []),
+                    7: (
+                        size: 12,
+                        name: "move",
+                        args: [Number, Number],
+                        argInfo: [(name: "x"), (name: "x"), (name: "speed y")],
+                    ),
+                }),
+            )"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let Err(BBScriptError::Multiple(errors)) = config.check() else {
+            panic!("config should fail to check");
+        };
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            errors,
+            [
+                "Jump table ID 1 is not an instruction with a `Begin` code block",
+                "Arg 0 of instruction ID 3 refers to enum `Facing`, which is missing from `named_value_maps`",
+                "Instruction name `wait:` can't be read back from readable BBScript",
+                "Instruction ID 5 is named `Unknown6`, which is how the unnamed instruction with ID 6 is written",
+                "Instruction ID 7 documents 3 args, but only has 2",
+                "Instruction ID 7 has more than one arg named `x`",
+                "Arg name of instruction ID 7 `speed y` can't be read back from readable BBScript",
+                "Variant of enum `Direction` ` Right` can't be read back from readable BBScript",
+                "Variant of enum `Direction` `Up/Down` can't be read back from readable BBScript",
+                "Variable name `1stHit` can't be read back from readable BBScript",
+                "Variable name `Pos(X)` can't be read back from readable BBScript",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn check_embedded_configs() {
+        use clap::ValueEnum;
+
+        for game in crate::SupportedGame::value_variants() {
+            if let Err(e) = game.into_config().check() {
+                panic!("Config of {game:?} failed to check: {e}");
+            }
+        }
+    }
 }
 
 #[cfg(feature = "old-cfg-converter")]
@@ -637,7 +987,9 @@ impl Into<SizedInstruction> for Function {
             size: self.size as usize,
             name: self.name,
             code_block: self.code_block,
+            symbol: Symbol::NoSymbol,
             args,
+            arg_info: Vec::new(),
             description: String::new(),
         }
     }
@@ -652,18 +1004,53 @@ pub enum Arg {
     Unknown(u32),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum CodeBlock {
     Begin,
     #[deprecated]
     #[cfg(feature = "old-cfg-converter")]
     BeginJumpEntry,
     End,
+    #[default]
     NoBlock,
 }
 
-impl Default for CodeBlock {
-    fn default() -> Self {
-        Self::NoBlock
+impl CodeBlock {
+    pub fn is_none(&self) -> bool {
+        *self == CodeBlock::NoBlock
+    }
+}
+
+/// Kinds of names that instructions can define and refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolKind {
+    State,
+    Subroutine,
+    /// Labels are local to the top level block they are defined in
+    Label,
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolKind::State => f.write_str("state"),
+            SymbolKind::Subroutine => f.write_str("subroutine"),
+            SymbolKind::Label => f.write_str("label"),
+        }
+    }
+}
+
+/// Whether an instruction defines or refers to names with its string arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Symbol {
+    Defines(SymbolKind),
+    References(SymbolKind),
+    #[default]
+    NoSymbol,
+}
+
+impl Symbol {
+    pub fn is_none(&self) -> bool {
+        *self == Symbol::NoSymbol
     }
 }
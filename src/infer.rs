@@ -0,0 +1,622 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use byteorder::ByteOrder;
+
+use crate::error::BBScriptError;
+use crate::game_config::{ArgType, InstructionInfo, ScriptConfig, SizedInstruction};
+use crate::parser::{has_plausible_strings, ArgValue};
+use crate::HashMap;
+
+/// Size of the words instructions are aligned to
+const WORD_SIZE: usize = 0x4;
+
+/// Bytes skipped while parsing a script, which begin with an unknown instruction ID
+#[derive(Debug, Clone)]
+struct UnknownRegion {
+    bytes: Vec<u8>,
+    /// Every aligned `u32` in the region, the first being the unknown ID
+    words: Vec<u32>,
+}
+
+impl UnknownRegion {
+    fn id(&self) -> u32 {
+        self.words[0]
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Splits off the region after the first instruction, if there is anything after it
+    fn split_at(&self, size: usize) -> Option<UnknownRegion> {
+        (size < self.len()).then(|| UnknownRegion {
+            bytes: self.bytes[size..].to_vec(),
+            words: self.words[size / WORD_SIZE..].to_vec(),
+        })
+    }
+}
+
+/// Sizes found for an instruction ID missing from a sized config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeInference {
+    pub instruction_id: u32,
+    /// Number of times the ID was found across all scripts
+    pub occurrences: usize,
+    /// Possible sizes paired with how many occurrences they are consistent with, most likely first
+    pub candidates: Vec<(usize, usize)>,
+}
+
+impl SizeInference {
+    /// The most likely size of the instruction
+    pub fn best(&self) -> Option<usize> {
+        self.candidates.first().map(|(size, _)| *size)
+    }
+
+    /// Creates a config entry using the most likely size, listing how it was inferred in the description
+    pub fn to_instruction(&self) -> Option<SizedInstruction> {
+        let mut instruction = SizedInstruction::new(self.best()?);
+
+        let (_, support) = self.candidates[0];
+        instruction.description = format!(
+            "inferred from {support} of {} occurrences",
+            self.occurrences
+        );
+
+        if self.candidates.len() > 1 {
+            let others = self.candidates[1..]
+                .iter()
+                .map(|(size, support)| format!("{size} ({support})"))
+                .collect::<Vec<_>>();
+            instruction.description += &format!(", other possible sizes: {}", others.join(", "));
+        }
+
+        Some(instruction)
+    }
+}
+
+/// Infers the sizes of instructions missing from a sized config, using where they sit between
+/// known instructions across many scripts.
+///
+/// An unknown instruction is never larger than the shortest run of bytes it was found at the start of,
+/// and within each run, a size is only possible if it is followed by a chain of known instructions
+/// reaching the end of the run, or by another unknown instruction
+#[derive(Debug, Clone)]
+pub struct SizeInferrer {
+    known: HashMap<u32, SizedInstruction>,
+    regions: Vec<UnknownRegion>,
+}
+
+impl SizeInferrer {
+    pub fn new(config: &ScriptConfig) -> Result<Self, BBScriptError> {
+        let InstructionInfo::Sized(instructions) = &config.instructions else {
+            return Err(BBScriptError::UnsupportedForUnsized(
+                "Size inference".into(),
+            ));
+        };
+
+        Ok(Self {
+            known: instructions.clone(),
+            regions: Vec::new(),
+        })
+    }
+
+    /// Parses a script, collecting the bytes of every unknown instruction in it
+    pub fn add_script<B: ByteOrder>(
+        &mut self,
+        config: &ScriptConfig,
+        script: &[u8],
+    ) -> Result<(), BBScriptError> {
+        let (_, skipped) = config.parse_recovering::<B>(script)?;
+
+        for skip in skipped {
+            let bytes = &script[skip.offset..skip.offset + skip.len];
+
+            self.regions.push(UnknownRegion {
+                bytes: bytes.to_vec(),
+                words: bytes.chunks_exact(WORD_SIZE).map(B::read_u32).collect(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Infers sizes for every unknown ID found, ordered by ID.
+    ///
+    /// When an unknown instruction is followed by another one, the second is only found
+    /// once the size of the first is known, so sizes are inferred until no more are found
+    pub fn infer(&self) -> Vec<SizeInference> {
+        // each region is paired with whether it has been split after its first instruction
+        let mut regions: Vec<(UnknownRegion, bool)> =
+            self.regions.iter().map(|r| (r.clone(), false)).collect();
+
+        loop {
+            let unknown_ids: BTreeSet<u32> = regions.iter().map(|(r, _)| r.id()).collect();
+            let inferences = self.infer_from_regions(regions.iter().map(|(r, _)| r), &unknown_ids);
+
+            let mut found = Vec::new();
+            for (region, split) in regions.iter_mut().filter(|(_, split)| !*split) {
+                if let Some(size) = inferences.get(&region.id()).and_then(SizeInference::best) {
+                    *split = true;
+                    found.extend(
+                        region
+                            .split_at(size)
+                            .filter(|rest| !self.known.contains_key(&rest.id())),
+                    );
+                }
+            }
+
+            if found.is_empty() {
+                return inferences.into_values().collect();
+            }
+
+            regions.extend(found.into_iter().map(|r| (r, false)));
+        }
+    }
+
+    fn infer_from_regions<'a>(
+        &self,
+        regions: impl Iterator<Item = &'a UnknownRegion>,
+        unknown_ids: &BTreeSet<u32>,
+    ) -> BTreeMap<u32, SizeInference> {
+        let mut by_id: BTreeMap<u32, Vec<&UnknownRegion>> = BTreeMap::new();
+        for region in regions {
+            by_id.entry(region.id()).or_default().push(region);
+        }
+
+        by_id
+            .into_iter()
+            .map(|(instruction_id, regions)| {
+                // `regions` is never empty
+                let max_size = regions.iter().map(|r| r.len()).min().unwrap();
+
+                let mut candidates: Vec<(usize, usize)> = (WORD_SIZE..=max_size)
+                    .step_by(WORD_SIZE)
+                    .chain((max_size % WORD_SIZE != 0).then_some(max_size))
+                    .map(|size| {
+                        let support = regions
+                            .iter()
+                            .filter(|region| {
+                                self.is_instruction_boundary(region, size, unknown_ids)
+                            })
+                            .count();
+
+                        (size, support)
+                    })
+                    .filter(|(_, support)| *support > 0)
+                    .collect();
+
+                // most supported first, preferring larger sizes as smaller ones are often args that look like IDs
+                candidates.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+
+                let inference = SizeInference {
+                    instruction_id,
+                    occurrences: regions.len(),
+                    candidates,
+                };
+
+                (instruction_id, inference)
+            })
+            .collect()
+    }
+
+    /// Checks if an instruction could end at `position` in a region, which is when the region ends there,
+    /// another unknown instruction starts there, or a chain of known instructions reaching the end of the region does
+    fn is_instruction_boundary(
+        &self,
+        region: &UnknownRegion,
+        mut position: usize,
+        unknown_ids: &BTreeSet<u32>,
+    ) -> bool {
+        loop {
+            if position == region.len() {
+                return true;
+            }
+
+            let Some(word) = region.words.get(position / WORD_SIZE) else {
+                return false;
+            };
+            if unknown_ids.contains(word) {
+                return true;
+            }
+
+            match self.known.get(word) {
+                Some(instruction)
+                    if instruction.size >= WORD_SIZE
+                        && position + instruction.size <= region.len()
+                        && has_plausible_strings(instruction, &region.bytes[position..]) =>
+                {
+                    position += instruction.size
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Most distinct values a `Number` arg can take while being suggested as an enum
+const MAX_ENUM_VARIANTS: usize = 8;
+/// Fewest times each value of a `Number` arg has to be seen for it to be suggested as an enum
+const MIN_ENUM_OCCURRENCES: usize = 2;
+
+/// Args suggested for the bytes of an `Unknown` arg, found by looking at every occurrence of its instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgInference {
+    pub instruction_id: u32,
+    /// Index of the `Unknown` arg within the instruction
+    pub arg_index: usize,
+    pub occurrences: usize,
+    pub args: Vec<ArgType>,
+    /// Observations that couldn't be expressed as an arg type, such as possible enums
+    pub notes: Vec<String>,
+}
+
+/// Suggests typed args for bytes that a config only knows as `Unknown` args.
+///
+/// Bytes are read as `String32` or `String16` if they hold printable text in every occurrence,
+/// `AccessedValue` if they start with the literal or variable tag, and `Number` otherwise.
+/// Numbers that take at least two but only a few values, each seen more than once,
+/// use an existing enum containing all of them if there is one
+#[derive(Debug, Clone, Default)]
+pub struct ArgInferrer {
+    /// Bytes of every occurrence of an `Unknown` arg, keyed by instruction ID and arg index
+    samples: BTreeMap<(u32, usize), Vec<Vec<u8>>>,
+}
+
+impl ArgInferrer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a script, collecting the bytes of every `Unknown` arg of instructions in the config
+    pub fn add_script<B: ByteOrder>(
+        &mut self,
+        config: &ScriptConfig,
+        script: &[u8],
+    ) -> Result<(), BBScriptError> {
+        let (program, _) = config.parse_recovering::<B>(script)?;
+
+        for (_, instruction) in program {
+            // instructions skipped while recovering have no config entry to patch
+            if !config.is_unsized() && config.get_by_id(instruction.id).is_none() {
+                continue;
+            }
+
+            for (index, arg) in instruction.args.iter().enumerate() {
+                if let ArgValue::Unknown(bytes) = arg {
+                    self.samples
+                        .entry((instruction.id, index))
+                        .or_default()
+                        .push(bytes.to_vec());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Suggests args for every `Unknown` arg found, ordered by instruction ID and arg index.
+    /// Args whose size differs between occurrences are skipped
+    pub fn infer<B: ByteOrder>(&self, config: &ScriptConfig) -> Vec<ArgInference> {
+        self.samples
+            .iter()
+            .filter_map(|(&(instruction_id, arg_index), samples)| {
+                let len = samples[0].len();
+                if samples.iter().any(|sample| sample.len() != len) {
+                    log::warn!(
+                        "arg {arg_index} of instruction {instruction_id} has a different size between occurrences"
+                    );
+                    return None;
+                }
+
+                let mut inference = ArgInference {
+                    instruction_id,
+                    arg_index,
+                    occurrences: samples.len(),
+                    args: Vec::new(),
+                    notes: Vec::new(),
+                };
+
+                let mut offset = 0;
+                while offset < len {
+                    let remaining = len - offset;
+                    let column =
+                        |size: usize| samples.iter().map(move |s| &s[offset..offset + size]);
+
+                    let arg = if remaining >= ArgType::STRING32_SIZE
+                        && is_string_column(column(ArgType::STRING32_SIZE))
+                    {
+                        ArgType::String32
+                    } else if remaining >= ArgType::STRING16_SIZE
+                        && is_string_column(column(ArgType::STRING16_SIZE))
+                    {
+                        ArgType::String16
+                    } else if remaining >= 8 && is_accessed_value_column::<B>(config, column(8)) {
+                        ArgType::AccessedValue
+                    } else if remaining >= 4 {
+                        let mut values: BTreeMap<i32, usize> = BTreeMap::new();
+                        for value in column(4).map(B::read_i32) {
+                            *values.entry(value).or_default() += 1;
+                        }
+                        let position = arg_index + inference.args.len();
+
+                        number_or_enum(config, &values, position, &mut inference.notes)
+                    } else {
+                        ArgType::Unknown(remaining)
+                    };
+
+                    offset += arg.size();
+                    inference.args.push(arg);
+                }
+
+                Some(inference)
+            })
+            .collect()
+    }
+
+    /// Creates a config patch holding every instruction with inferred args,
+    /// with `Unknown` args replaced and notes added to their descriptions
+    pub fn patch<B: ByteOrder>(&self, config: &ScriptConfig) -> InstructionInfo {
+        let mut by_id: BTreeMap<u32, Vec<ArgInference>> = BTreeMap::new();
+        for inference in self.infer::<B>(config) {
+            by_id
+                .entry(inference.instruction_id)
+                .or_default()
+                .push(inference);
+        }
+
+        match &config.instructions {
+            InstructionInfo::Sized(instructions) => InstructionInfo::Sized(
+                by_id
+                    .into_iter()
+                    .filter_map(|(id, inferences)| {
+                        let mut instruction = instructions.get(&id)?.clone();
+                        let args = replace_args(instruction.args().to_vec(), &inferences);
+
+                        instruction.set_args(args.into());
+                        add_notes(&mut instruction.description, &inferences);
+
+                        Some((id, instruction))
+                    })
+                    .collect(),
+            ),
+            InstructionInfo::Unsized(instructions) => InstructionInfo::Unsized(
+                by_id
+                    .into_iter()
+                    .map(|(id, inferences)| {
+                        let mut instruction = instructions.get(&id).cloned().unwrap_or_default();
+
+                        // the bytes not covered by the config are read as one extra arg
+                        let mut args = instruction.args.to_vec();
+                        args.push(ArgType::Unknown(0));
+
+                        instruction.args = replace_args(args, &inferences).into();
+                        add_notes(&mut instruction.description, &inferences);
+
+                        (id, instruction)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Replaces `Unknown` args with inferred ones, dropping a trailing `Unknown` arg
+/// as the bytes after the args in the config are always read as one
+fn replace_args(mut args: Vec<ArgType>, inferences: &[ArgInference]) -> Vec<ArgType> {
+    for inference in inferences.iter().rev() {
+        args.splice(
+            inference.arg_index..=inference.arg_index,
+            inference.args.iter().cloned(),
+        );
+    }
+
+    if let Some(ArgType::Unknown(_)) = args.last() {
+        args.pop();
+    }
+
+    args
+}
+
+fn add_notes(description: &mut String, inferences: &[ArgInference]) {
+    for note in inferences.iter().flat_map(|i| &i.notes) {
+        if !description.is_empty() {
+            description.push_str("; ");
+        }
+        description.push_str(note);
+    }
+}
+
+/// Checks that every sample is printable text padded with zeros, and that at least one isn't empty
+fn is_string_column<'a>(mut samples: impl Iterator<Item = &'a [u8]> + Clone) -> bool {
+    let is_string = |bytes: &[u8]| {
+        let text_len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+
+        bytes[..text_len].iter().all(|b| (0x20..0x7F).contains(b))
+            && bytes[text_len..].iter().all(|b| *b == 0)
+    };
+
+    samples.clone().all(is_string) && samples.any(|bytes| bytes[0] != 0)
+}
+
+/// Checks that every sample starts with the literal or variable tag, and that at least one is a variable,
+/// as the literal tag is usually 0 and would match most numbers
+fn is_accessed_value_column<'a, B: ByteOrder>(
+    config: &ScriptConfig,
+    mut samples: impl Iterator<Item = &'a [u8]> + Clone,
+) -> bool {
+    let tags = samples.clone().map(B::read_i32);
+
+    tags.clone()
+        .all(|tag| tag == config.literal_tag || tag == config.variable_tag)
+        && samples.any(|bytes| B::read_i32(bytes) == config.variable_tag)
+}
+
+/// Suggests an enum for a number that only takes a few values, noting when no existing enum fits.
+/// `values` counts how many times each value was seen.
+///
+/// A number that never changes, or has a value seen only once, doesn't have enough samples to tell it
+/// apart from a plain number
+fn number_or_enum(
+    config: &ScriptConfig,
+    values: &BTreeMap<i32, usize>,
+    position: usize,
+    notes: &mut Vec<String>,
+) -> ArgType {
+    if !(2..=MAX_ENUM_VARIANTS).contains(&values.len())
+        || values.values().any(|count| *count < MIN_ENUM_OCCURRENCES)
+    {
+        return ArgType::Number;
+    }
+
+    // the smallest enum holding every value is the most specific
+    let existing = config
+        .named_value_maps
+        .iter()
+        .filter(|(_, map)| values.keys().all(|value| map.contains_left(value)))
+        .min_by_key(|(name, map)| (map.len(), name.to_string()));
+
+    match existing {
+        Some((name, _)) => ArgType::Enum(name.clone()),
+        None => {
+            let values = values.keys().map(|v| v.to_string()).collect::<Vec<_>>();
+            notes.push(format!(
+                "arg {position} only takes the values {} and may be an enum",
+                values.join(", ")
+            ));
+
+            ArgType::Number
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArgInferrer, SizeInferrer};
+    use crate::game_config::{ArgType, InstructionInfo, ScriptConfig};
+    use crate::rebuilder::rebuild_bbscript;
+    use byteorder::LittleEndian;
+
+    #[test]
+    fn infer_missing_sizes() {
+        let mut config = ScriptConfig::load("./static_db/ggst.ron").unwrap();
+        let script = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'
+  sprite: s32'a', 100000
+  jumpToState: s32'B'
+  exitState:
+  sprite: s32'b', 100000
+  sprite: s32'c', 100000
+endState:
+beginState: s32'B'
+  jumpToState: s32'A'
+  sprite: s32'd', 100000
+  sprite: s32'e', 100000
+endState:"
+                .into(),
+        )
+        .unwrap();
+
+        // remove `jumpToState` and `exitState`, which is only found after `jumpToState`
+        let InstructionInfo::Sized(instructions) = &mut config.instructions else {
+            unreachable!()
+        };
+        instructions.remove(&27);
+        instructions.remove(&18);
+
+        let mut inferrer = SizeInferrer::new(&config).unwrap();
+        inferrer
+            .add_script::<LittleEndian>(&config, &script)
+            .unwrap();
+
+        let sizes: Vec<_> = inferrer
+            .infer()
+            .iter()
+            .map(|i| (i.instruction_id, i.occurrences, i.best()))
+            .collect();
+        assert_eq!(sizes, [(18, 1, Some(4)), (27, 2, Some(36))]);
+    }
+
+    #[test]
+    fn infer_unknown_args() {
+        let mut config = ScriptConfig::load("./static_db/ggst.ron").unwrap();
+        let script = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'
+  beginLabel: s32'Loop'
+  gotoIfOperation: s32'Loop', 12, Mem(5), Val(1)
+  gotoIfOperation: s32'Loop', 15, Val(3), Mem(6)
+  gotoIfOperation: s32'End', 12, Mem(7), Val(-1)
+  gotoIfOperation: s32'End', 15, Mem(8), Val(2)
+  beginLabel: s32'End'
+endState:"
+                .into(),
+        )
+        .unwrap();
+
+        // forget the args of `gotoIfOperation`, leaving all of its bytes unknown
+        let InstructionInfo::Sized(instructions) = &mut config.instructions else {
+            unreachable!()
+        };
+        instructions
+            .get_mut(&13)
+            .unwrap()
+            .set_args(Default::default());
+
+        let mut inferrer = ArgInferrer::new();
+        inferrer
+            .add_script::<LittleEndian>(&config, &script)
+            .unwrap();
+
+        let inferences = inferrer.infer::<LittleEndian>(&config);
+        assert_eq!(inferences.len(), 1);
+        assert_eq!(
+            inferences[0].args,
+            [
+                ArgType::String32,
+                ArgType::Enum("OPERATION".into()),
+                ArgType::AccessedValue,
+                ArgType::AccessedValue
+            ]
+        );
+
+        let InstructionInfo::Sized(patch) = inferrer.patch::<LittleEndian>(&config) else {
+            unreachable!()
+        };
+        assert_eq!(patch[&13].args().as_slice(), inferences[0].args.as_slice());
+    }
+
+    #[test]
+    fn constant_number_is_not_enum() {
+        let mut config = ScriptConfig::load("./static_db/ggst.ron").unwrap();
+        let script = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'
+  beginLabel: s32'Loop'
+  gotoIfOperation: s32'Loop', 0, Mem(5), Val(1)
+  gotoIfOperation: s32'Loop', 0, Val(3), Mem(6)
+  gotoIfOperation: s32'Loop', 0, Mem(7), Val(-1)
+endState:"
+                .into(),
+        )
+        .unwrap();
+
+        let InstructionInfo::Sized(instructions) = &mut config.instructions else {
+            unreachable!()
+        };
+        instructions
+            .get_mut(&13)
+            .unwrap()
+            .set_args(Default::default());
+
+        let mut inferrer = ArgInferrer::new();
+        inferrer
+            .add_script::<LittleEndian>(&config, &script)
+            .unwrap();
+
+        // every enum with a 0 variant would fit a column that's always 0
+        let inferences = inferrer.infer::<LittleEndian>(&config);
+        assert_eq!(inferences[0].args[1], ArgType::Number);
+        assert!(inferences[0].notes.is_empty());
+    }
+}
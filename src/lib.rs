@@ -0,0 +1,97 @@
+//! Parsing and rebuilding of Arc System Works BBScript files.
+//!
+//! A [`ScriptConfig`] describes the instructions of a single game, and is used both to
+//! parse binary scripts into [`InstructionValue`]s or readable text with
+//! [`ScriptConfig::parse`] and [`ScriptConfig::parse_to_string`], and to turn readable
+//! text back into a binary script with [`rebuild_bbscript`].
+//! Scripts can also be parsed into a [`ScriptTree`] of nested blocks to be inspected or modified.
+//! Configs for supported games are embedded and available through [`SupportedGame`].
+
+pub mod ast;
+pub mod config_diff;
+pub mod container;
+pub mod docs;
+pub mod error;
+pub mod export;
+pub mod formatter;
+pub mod framedata;
+pub mod game_config;
+pub mod infer;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod overlay;
+pub mod parser;
+pub mod rebuilder;
+pub mod verify;
+pub mod vm;
+
+extern crate pest_derive;
+
+pub use crate::ast::{ScriptBlock, ScriptNode, ScriptTree};
+pub use crate::container::Container;
+pub use crate::docs::DocsFormat;
+pub use crate::error::{BBScriptError, SourceSpan};
+pub use crate::export::{rebuild_from_format, ScriptFormat};
+pub use crate::game_config::ScriptConfig;
+pub use crate::overlay::ConfigOverlay;
+pub use crate::parser::{ArgValue, InstructionValue, TextOptions};
+pub use crate::rebuilder::{rebuild_bbscript, rebuild_bbscript_with_symbols, ExternalSymbols};
+pub use crate::verify::verify_round_trip;
+pub use crate::vm::StateVm;
+
+pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V>;
+
+const BBCF_CONFIG: &str = include_str!("../static_db/bbcf.ron");
+const DBFZ_CONFIG: &str = include_str!("../static_db/dbfz.ron");
+const DNF_CONFIG: &str = include_str!("../static_db/dnf.ron");
+const GBVS_CONFIG: &str = include_str!("../static_db/gbvs.ron");
+const GBVSR_CONFIG: &str = include_str!("../static_db/gbvsr.ron");
+const GGREV2_CONFIG: &str = include_str!("../static_db/ggrev2.ron");
+const GGST_CONFIG: &str = include_str!("../static_db/ggst.ron");
+const P4U2_CONFIG: &str = include_str!("../static_db/p4u2.ron");
+
+/// Games with a config embedded in BBScript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SupportedGame {
+    /// Blazblue: Centralfiction
+    Bbcf,
+    /// Dragon Ball FighterZ
+    Dbfz,
+    /// DNF Duel
+    Dnf,
+    /// Granblue Fantasy Versus
+    Gbvs,
+    /// Granblue Fantasy Versus: Rising
+    Gbvsr,
+    /// Guilty Gear Xrd Rev2
+    Ggrev2,
+    /// Guilty Gear Strive
+    Ggst,
+    /// Persona 4 Arena Ultimax
+    P4u2,
+}
+
+impl SupportedGame {
+    /// Returns the embedded RON source of this game's config
+    pub const fn config_source(self) -> &'static str {
+        match self {
+            SupportedGame::Bbcf => BBCF_CONFIG,
+            SupportedGame::Dbfz => DBFZ_CONFIG,
+            SupportedGame::Dnf => DNF_CONFIG,
+            SupportedGame::Gbvs => GBVS_CONFIG,
+            SupportedGame::Gbvsr => GBVSR_CONFIG,
+            SupportedGame::Ggrev2 => GGREV2_CONFIG,
+            SupportedGame::Ggst => GGST_CONFIG,
+            SupportedGame::P4u2 => P4U2_CONFIG,
+        }
+    }
+
+    pub fn into_config(self) -> ScriptConfig {
+        let result = ScriptConfig::new(self.config_source().as_bytes());
+
+        // all embedded configs should parse correctly so this should be infallible
+        result.unwrap()
+    }
+}
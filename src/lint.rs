@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BBScriptError, SourceSpan};
+use crate::game_config::{ArgType, CodeBlock, ScriptConfig, Symbol, SymbolKind};
+use crate::rebuilder::{
+    describe_arg, find_instruction_info, parse_program, resolve_arg_names, ParserValue,
+};
+
+/// Mistakes in a readable script that still rebuild, but are unlikely to be intended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// An instruction outside of any top level block, such as one after a state has ended
+    OutsideState,
+    /// A block that is never ended, or the end of a block that was never begun.
+    ///
+    /// Blocks are paired by depth alone, the same as when parsing, since configs don't say which end
+    /// instruction belongs to which begin instruction. An end in the wrong place closes the innermost
+    /// open block regardless of its name, so the lint is reported where the blocks run out instead,
+    /// such as at the begin of a state whose `endState` closed an `if` inside of it
+    UnpairedBlock,
+    /// A label that nothing in its top level block jumps to
+    UnusedLabel,
+    /// A `Mem()` name that isn't one of the config's `named_variables`
+    UnknownVariable,
+    /// A `BadTag()` given to an arg that the config reads as a tagged value
+    BadTag,
+    /// A raw arg whose length differs from the unknown bytes the config leaves for it
+    UnknownSize,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // names are the same as the fields of a lint config
+        f.write_str(match self {
+            Lint::OutsideState => "outside_state",
+            Lint::UnpairedBlock => "unpaired_block",
+            Lint::UnusedLabel => "unused_label",
+            Lint::UnknownVariable => "unknown_variable",
+            Lint::BadTag => "bad_tag",
+            Lint::UnknownSize => "unknown_size",
+        })
+    }
+}
+
+/// How a lint is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LintLevel::Allow => "allowed",
+            LintLevel::Warn => "warning",
+            LintLevel::Deny => "error",
+        })
+    }
+}
+
+/// The level of each lint, read from a RON file such as `(unused_label: Allow, bad_tag: Deny)`.
+///
+/// Lints left out of the file keep their default level, which is `Deny` for unpaired blocks
+/// and unknown variables since they break the script, and `Warn` for the rest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub outside_state: LintLevel,
+    pub unpaired_block: LintLevel,
+    pub unused_label: LintLevel,
+    pub unknown_variable: LintLevel,
+    pub bad_tag: LintLevel,
+    pub unknown_size: LintLevel,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            outside_state: LintLevel::Warn,
+            unpaired_block: LintLevel::Deny,
+            unused_label: LintLevel::Warn,
+            unknown_variable: LintLevel::Deny,
+            bad_tag: LintLevel::Warn,
+            unknown_size: LintLevel::Warn,
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn new<T: Read>(config: T) -> Result<Self, BBScriptError> {
+        ron::de::from_reader(config).map_err(|e| BBScriptError::ConfigInvalid(e.to_string()))
+    }
+
+    pub fn load<T: AsRef<Path>>(config_path: T) -> Result<Self, BBScriptError> {
+        let config_file = File::open(&config_path).map_err(|e| {
+            BBScriptError::ConfigOpenError(
+                format!("{}", config_path.as_ref().display()),
+                e.to_string(),
+            )
+        })?;
+
+        Self::new(config_file)
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        match lint {
+            Lint::OutsideState => self.outside_state,
+            Lint::UnpairedBlock => self.unpaired_block,
+            Lint::UnusedLabel => self.unused_label,
+            Lint::UnknownVariable => self.unknown_variable,
+            Lint::BadTag => self.bad_tag,
+            Lint::UnknownSize => self.unknown_size,
+        }
+    }
+}
+
+/// A lint found in a readable script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintMessage {
+    pub lint: Lint,
+    pub level: LintLevel,
+    pub message: String,
+    pub span: Option<SourceSpan>,
+}
+
+impl LintMessage {
+    /// Sets the file name shown when displaying the location of the lint
+    pub fn with_file(mut self, file: impl AsRef<str>) -> Self {
+        if let Some(span) = &mut self.span {
+            span.file = Some(file.as_ref().to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for LintMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.level, self.lint, self.message)?;
+        match &self.span {
+            Some(span) => write!(f, "\n{span}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ScriptConfig {
+    /// Checks a readable script for mistakes that rebuilding it would accept,
+    /// returning the lints that aren't allowed by `lints` in the order they appear in the script.
+    ///
+    /// Returns an error if the script can't be parsed, uses instructions missing from the config,
+    /// or has args that can't be put in order
+    pub fn lint_script(
+        &self,
+        script: &str,
+        lints: &LintConfig,
+    ) -> Result<Vec<LintMessage>, BBScriptError> {
+        let mut program = parse_program(script)?;
+
+        let mut errors = resolve_arg_names(&mut program, self, script);
+        let mut infos = Vec::new();
+        for function in &program {
+            match find_instruction_info(self, function, script) {
+                Ok(info) => infos.push(info),
+                Err(e) => errors.push(e),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(BBScriptError::from_errors(errors));
+        }
+
+        let mut found: Vec<(Lint, String, Option<Range<usize>>)> = Vec::new();
+
+        // begun blocks that haven't ended yet, innermost last. Any end closes the innermost block
+        let mut open_blocks = Vec::new();
+        // the instruction that ended the previous top level block, if any
+        let mut previous_end = None;
+        // labels are keyed by the index of the top level block they're in, the same as when rebuilding
+        let mut top_level_block = 0;
+        let mut labels = Vec::new();
+        let mut jumps = HashSet::new();
+
+        for (function, info) in program.iter().zip(&infos) {
+            match info.block_type() {
+                CodeBlock::Begin => {
+                    if open_blocks.is_empty() {
+                        top_level_block += 1;
+                    }
+                    open_blocks.push(function);
+                }
+                CodeBlock::End => {
+                    if open_blocks.pop().is_none() {
+                        let message =
+                            format!("`{}` ends a block that was never begun", function.name);
+                        found.push((Lint::UnpairedBlock, message, function.span.clone()));
+                    } else if open_blocks.is_empty() {
+                        previous_end = Some(function);
+                    }
+                }
+                _ if open_blocks.is_empty() => {
+                    let message = match previous_end {
+                        Some(end) => format!(
+                            "`{}` comes after `{}` and is outside of any state",
+                            function.name, end.name
+                        ),
+                        None => format!("`{}` is outside of any state", function.name),
+                    };
+                    found.push((Lint::OutsideState, message, function.span.clone()));
+                }
+                _ => {}
+            }
+
+            for name in function.symbol_names() {
+                match info.symbol() {
+                    Symbol::Defines(SymbolKind::Label) => {
+                        labels.push((top_level_block, name, function));
+                    }
+                    Symbol::References(SymbolKind::Label) => {
+                        jumps.insert((top_level_block, name));
+                    }
+                    _ => {}
+                }
+            }
+
+            let arg_types = info.all_args();
+            for (index, arg) in function.args.iter().enumerate() {
+                let arg_type = arg_types.get(index);
+                let describe = || describe_arg(info, index);
+
+                let lint = match arg {
+                    ParserValue::NamedMem(name)
+                        if self.get_variable_by_name(name.clone()).is_none() =>
+                    {
+                        let message = format!(
+                            "Arg {} of `{}` reads `{name}`, which isn't a named variable in the config",
+                            describe(),
+                            function.name
+                        );
+                        Some((Lint::UnknownVariable, message))
+                    }
+                    &ParserValue::BadTag(tag, value)
+                        if arg_type == Some(&ArgType::AccessedValue) =>
+                    {
+                        let message = if tag == self.literal_tag {
+                            format!(
+                                "`{arg}` has the literal tag and can be written as `Val({value})`"
+                            )
+                        } else if tag == self.variable_tag {
+                            format!(
+                                "`{arg}` has the variable tag and can be written as `Mem({value})`"
+                            )
+                        } else {
+                            format!(
+                                "Arg {} of `{}` has tag {tag}, which is neither the literal tag {} nor the variable tag {}",
+                                describe(),
+                                function.name,
+                                self.literal_tag,
+                                self.variable_tag
+                            )
+                        };
+                        Some((Lint::BadTag, message))
+                    }
+                    ParserValue::Raw(data) => match arg_type {
+                        Some(&ArgType::Unknown(size)) if size != data.len() => {
+                            let message = format!(
+                                "Arg {} of `{}` is {} bytes, but the config leaves {size} unknown bytes for it",
+                                describe(),
+                                function.name,
+                                data.len()
+                            );
+                            Some((Lint::UnknownSize, message))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                if let Some((lint, message)) = lint {
+                    found.push((lint, message, function.arg_span(index)));
+                }
+            }
+        }
+
+        for (scope, name, function) in labels {
+            if !jumps.contains(&(scope, name)) {
+                let message = format!("Label `{name}` is never jumped to");
+                found.push((Lint::UnusedLabel, message, function.span.clone()));
+            }
+        }
+        for function in open_blocks {
+            let message = format!("Block begun by `{}` is never ended", function.name);
+            found.push((Lint::UnpairedBlock, message, function.span.clone()));
+        }
+
+        found.sort_by_key(|(_, _, span)| span.as_ref().map(|span| span.start));
+
+        Ok(found
+            .into_iter()
+            .filter(|(lint, ..)| lints.level(*lint) != LintLevel::Allow)
+            .map(|(lint, message, span)| LintMessage {
+                lint,
+                level: lints.level(lint),
+                message,
+                span: span.map(|span| SourceSpan::new(script, span)),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Lint, LintConfig, LintLevel};
+    use crate::game_config::test::config;
+
+    #[test]
+    fn lint_script() {
+        let script = "\
+store: Val(1)
+beginState: s32'A'
+  label: s16'used'
+  label: s16'unused'
+  gotoLabel: s16'used'
+  store: from=Mem(Missing)
+  store: Mem(Hits)
+  store: BadTag(5, 1)
+  store: BadTag(0, 1)
+  pad: 1, 0x00
+  pad: 1, 0x00000000
+endState:
+endState:
+pad: 1, 0x00000000
+beginState: s32'B'
+  label: s16'used'
+  if:
+endState:
+";
+        let found: Vec<_> = config()
+            .lint_script(script, &LintConfig::default())
+            .unwrap()
+            .into_iter()
+            .map(|lint| {
+                let span = lint.span.unwrap();
+                (lint.lint, lint.level, span.line, span.column)
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                (Lint::OutsideState, LintLevel::Warn, 1, 1),
+                (Lint::UnusedLabel, LintLevel::Warn, 4, 3),
+                (Lint::UnknownVariable, LintLevel::Deny, 6, 10),
+                (Lint::BadTag, LintLevel::Warn, 8, 10),
+                (Lint::BadTag, LintLevel::Warn, 9, 10),
+                (Lint::UnknownSize, LintLevel::Warn, 10, 11),
+                (Lint::UnpairedBlock, LintLevel::Deny, 13, 1),
+                (Lint::OutsideState, LintLevel::Warn, 14, 1),
+                // the state's end closes the `if` instead
+                (Lint::UnpairedBlock, LintLevel::Deny, 15, 1),
+                (Lint::UnusedLabel, LintLevel::Warn, 16, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_levels() {
+        let lints =
+            LintConfig::new("(unused_label: Allow, outside_state: Deny)".as_bytes()).unwrap();
+        assert_eq!(lints.level(Lint::UnusedLabel), LintLevel::Allow);
+        assert_eq!(lints.level(Lint::OutsideState), LintLevel::Deny);
+        // lints left out keep their default level
+        assert_eq!(lints.level(Lint::BadTag), LintLevel::Warn);
+        assert!(LintConfig::new("(unused_labels: Allow)".as_bytes()).is_err());
+
+        let script = "pad: 1, 0x00000000\nbeginState: s32'A'\n  label: s16'loop'\nendState:";
+        let found = config().lint_script(script, &lints).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].lint, Lint::OutsideState);
+        assert_eq!(found[0].level, LintLevel::Deny);
+        assert_eq!(
+            found[0].to_string(),
+            "error[outside_state]: `pad` is outside of any state\n \
+             --> 1:1\n  |\n1 | pad: 1, 0x00000000\n  | ^^^^^^^^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn untyped_enum_variant() {
+        let script = "beginState: s32'A'\n  Unknown99999: (Left)\nendState:";
+        let error = config()
+            .lint_script(script, &LintConfig::default())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Enum variant `Left` can't be used by an instruction missing"));
+        assert_eq!(error.span().unwrap().line, 2);
+    }
+}
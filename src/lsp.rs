@@ -0,0 +1,979 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ops::Range;
+
+use byteorder::LittleEndian;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    CompletionTextEdit, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, Documentation, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, OneOf, Position, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+
+use crate::error::BBScriptError;
+use crate::game_config::{
+    ArgType, CodeBlock, GenericInstruction, ScriptConfig, Symbol, SymbolKind,
+};
+use crate::rebuilder::{comment_ranges, rebuild_bbscript_unchecked};
+use crate::HashMap;
+
+type ServerResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Runs a language server for readable scripts over stdin and stdout until the client shuts it down.
+///
+/// The server offers completion of instruction names, enum variants and variable names, hover documentation
+/// from the config, go-to-definition of referenced states, subroutines and labels, and diagnostics
+/// from rebuilding each open script
+pub fn run_stdio(config: ScriptConfig) -> ServerResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["(".into(), ",".into()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        index: ConfigIndex::new(config),
+        documents: HashMap::new(),
+    };
+    server.run(&connection)?;
+
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
+
+struct Server {
+    index: ConfigIndex,
+    /// Text of every document open in the client
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> ServerResult<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.respond(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(diagnostics) = self.update_document(notification)? {
+                        connection.sender.send(Message::Notification(diagnostics))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn respond(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            Completion::METHOD => {
+                serde_json::from_value(request.params).map(|params: CompletionParams| {
+                    let position = params.text_document_position;
+                    self.documents
+                        .get(&position.text_document.uri)
+                        .map(|text| completions(&self.index, text, position.position))
+                        .map(CompletionResponse::Array)
+                        .and_then(|response| serde_json::to_value(response).ok())
+                })
+            }
+            HoverRequest::METHOD => {
+                serde_json::from_value(request.params).map(|params: HoverParams| {
+                    let position = params.text_document_position_params;
+                    self.documents
+                        .get(&position.text_document.uri)
+                        .and_then(|text| hover(&self.index, text, position.position))
+                        .and_then(|hover| serde_json::to_value(hover).ok())
+                })
+            }
+            GotoDefinition::METHOD => {
+                serde_json::from_value(request.params).map(|params: GotoDefinitionParams| {
+                    let position = params.text_document_position_params;
+                    self.definition(&position.text_document.uri, position.position)
+                        .map(GotoDefinitionResponse::Scalar)
+                        .and_then(|response| serde_json::to_value(response).ok())
+                })
+            }
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request `{method}`"),
+                )
+            }
+        };
+
+        match result {
+            Ok(Some(value)) => Response::new_ok(id, value),
+            Ok(None) => Response::new_ok(id, serde_json::Value::Null),
+            Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    /// Keeps track of open documents, returning new diagnostics for a document that was opened, changed or closed
+    fn update_document(
+        &mut self,
+        notification: Notification,
+    ) -> ServerResult<Option<Notification>> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // documents are synced in full, so the last change holds the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // diagnostics of closed documents are cleared, since they are no longer kept up to date
+                self.documents.remove(&uri);
+                uri
+            }
+            _ => return Ok(None),
+        };
+
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .map(|text| diagnostics(&self.index.config, text))
+            .unwrap_or_default();
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+
+        Ok(Some(Notification::new(
+            PublishDiagnostics::METHOD.into(),
+            params,
+        )))
+    }
+
+    /// Finds the definition of the name referenced at `position`, looking through other open documents
+    /// for states and subroutines defined outside of the current one, such as in a common script
+    fn definition(&self, uri: &Url, position: Position) -> Option<Location> {
+        let text = self.documents.get(uri)?;
+        let (kind, name) = referenced_symbol(&self.index, text, position)?;
+
+        if let Some(range) = find_definition(&self.index, text, kind, &name, position.line) {
+            return Some(Location::new(uri.clone(), range));
+        }
+        if kind == SymbolKind::Label {
+            return None;
+        }
+
+        self.documents
+            .iter()
+            .filter(|(other, _)| *other != uri)
+            .find_map(|(other, text)| {
+                find_definition(&self.index, text, kind, &name, 0)
+                    .map(|range| Location::new(other.clone(), range))
+            })
+    }
+}
+
+/// A config along with its instructions indexed by the name they are written with
+struct ConfigIndex {
+    config: ScriptConfig,
+    by_id: BTreeMap<u32, GenericInstruction>,
+    by_name: HashMap<String, u32>,
+}
+
+impl ConfigIndex {
+    fn new(config: ScriptConfig) -> Self {
+        let by_id: BTreeMap<u32, GenericInstruction> = config
+            .instructions
+            .iter_generic()
+            .filter_map(|(id, _)| config.get_by_id(id).map(|i| (id, i)))
+            .collect();
+        let by_name = by_id
+            .iter()
+            .map(|(id, instruction)| (instruction.display_name(), *id))
+            .collect();
+
+        Self {
+            config,
+            by_id,
+            by_name,
+        }
+    }
+
+    /// Finds an instruction by name, or by ID for `Unknown` instructions, the same as the rebuilder
+    fn instruction(&self, name: &str) -> Option<&GenericInstruction> {
+        let id = match self.by_name.get(name) {
+            Some(id) => *id,
+            None => name.strip_prefix("Unknown")?.parse().ok()?,
+        };
+
+        self.by_id.get(&id)
+    }
+}
+
+/// An instruction on a single line of a readable script, with byte offsets relative to the start of the line
+#[derive(Debug)]
+struct ScannedLine<'a> {
+    name: &'a str,
+    name_start: usize,
+    /// The last arg is empty if the line ends with a comma, or has no args after its colon
+    args: Vec<ScannedArg<'a>>,
+}
+
+#[derive(Debug)]
+struct ScannedArg<'a> {
+    /// Name the arg is given as `name=value`
+    name: Option<&'a str>,
+    value: &'a str,
+    value_start: usize,
+    /// Bytes covered by the whole arg, including its name
+    span: Range<usize>,
+}
+
+impl ScannedLine<'_> {
+    fn name_span(&self) -> Range<usize> {
+        self.name_start..self.name_start + self.name.len()
+    }
+
+    /// Returns the position of an arg within the instruction's args, which is given by its name if it has one
+    fn arg_position(&self, index: usize, info: &GenericInstruction) -> Option<usize> {
+        match self.args.get(index)?.name {
+            Some(name) => info.arg_info().iter().position(|arg| arg.name == name),
+            None => Some(index),
+        }
+    }
+
+    /// The names defined or referenced by the instruction, which are its string args
+    fn symbol_names(&self) -> impl Iterator<Item = (String, Range<usize>)> + '_ {
+        self.args
+            .iter()
+            .filter_map(|arg| string_value(arg.value).map(|name| (name, arg.span.clone())))
+    }
+}
+
+/// Splits a line with comments already masked the way the grammar would,
+/// while also accepting incomplete lines that are still being typed.
+/// Returns `None` for lines without an instruction
+fn scan_line(line: &str) -> Option<ScannedLine<'_>> {
+    let name_start = line.len() - line.trim_start().len();
+    // instruction names can't contain quotes, so the first colon always ends the name
+    let colon = line.find(':')?;
+
+    let mut args = Vec::new();
+    let mut arg_start = colon + 1;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut chars = line[arg_start..].char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let offset = offset + colon + 1;
+        match c {
+            // only quotes can be escaped within strings
+            '\\' if in_string && chars.peek().is_some_and(|(_, next)| *next == '\'') => {
+                chars.next();
+            }
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                args.push(scan_arg(line, arg_start..offset));
+                arg_start = offset + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(scan_arg(line, arg_start..line.len()));
+
+    Some(ScannedLine {
+        name: &line[name_start..colon],
+        name_start,
+        args,
+    })
+}
+
+fn scan_arg(line: &str, range: Range<usize>) -> ScannedArg<'_> {
+    let text = &line[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = (range.start + text.trim_end().len()).max(start);
+    let text = &line[start..end];
+
+    let name_len = text
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_alphabetic() || *c == '_' || (*i > 0 && c.is_ascii_digit())))
+        .map_or(text.len(), |(i, _)| i);
+    let rest = &text[name_len..];
+    let (name, value_start) = match rest.trim_start().strip_prefix('=') {
+        Some(value) if name_len > 0 => (Some(&text[..name_len]), end - value.trim_start().len()),
+        _ => (None, start),
+    };
+
+    ScannedArg {
+        name,
+        value: &line[value_start..end],
+        value_start,
+        span: start..end,
+    }
+}
+
+/// Reads the text of a `s32'...'` or `s16'...'` arg
+fn string_value(value: &str) -> Option<String> {
+    let text = value
+        .strip_prefix("s32'")
+        .or_else(|| value.strip_prefix("s16'"))?
+        .strip_suffix('\'')?;
+
+    Some(text.replace("\\'", "'"))
+}
+
+/// Replaces comments with spaces, keeping every other byte at the same offset.
+/// Returns the masked text and whether it ends within a comment
+fn mask_comments(text: &str) -> (String, bool) {
+    let comments = comment_ranges(text);
+
+    let mut masked = text.as_bytes().to_vec();
+    for range in &comments {
+        for byte in &mut masked[range.clone()] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    let in_comment = comments.last().is_some_and(|last| {
+        let comment = &text[last.clone()];
+        let closed = comment.starts_with("/*") && comment.len() >= 4 && comment.ends_with("*/");
+        last.end == text.len() && !closed
+    });
+
+    // comments only ever start and end on character boundaries
+    (String::from_utf8(masked).unwrap(), in_comment)
+}
+
+/// Splits text into lines the way LSP clients count them
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n').map(|line| line.trim_end_matches('\r'))
+}
+
+/// Converts a UTF-16 column, which LSP positions use, into a byte offset within `line`
+fn byte_column(line: &str, character: u32) -> usize {
+    let mut utf16 = 0;
+    for (offset, c) in line.char_indices() {
+        if utf16 >= character as usize {
+            return offset;
+        }
+        utf16 += c.len_utf16();
+    }
+    line.len()
+}
+
+/// Converts a 1-based column and length counted in characters, which errors use, into bytes of `line`
+fn char_range(line: &str, column: usize, len: usize) -> Range<usize> {
+    let start = line
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line.len(), |(offset, _)| offset);
+    let end = line[start..]
+        .char_indices()
+        .nth(len)
+        .map_or(line.len(), |(offset, _)| start + offset);
+
+    start..end
+}
+
+/// Converts a byte offset within `line` into a UTF-16 column
+fn utf16_column(line: &str, offset: usize) -> u32 {
+    line[..offset.min(line.len())]
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>() as u32
+}
+
+fn lsp_range(line_text: &str, line: u32, range: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(
+        Position::new(line, utf16_column(line_text, range.start)),
+        Position::new(line, utf16_column(line_text, range.end)),
+    )
+}
+
+/// The masked and original text of the line at `position`, along with the byte offset of the position
+fn line_at(text: &str, position: Position) -> Option<(String, &str, usize)> {
+    let (masked, _) = mask_comments(text);
+    let masked = lines(&masked).nth(position.line as usize)?.to_string();
+    let original = lines(text).nth(position.line as usize)?;
+    let column = byte_column(original, position.character);
+
+    Some((masked, original, column))
+}
+
+fn arg_type_name(arg: &ArgType) -> String {
+    match arg {
+        ArgType::Enum(name) => name.clone(),
+        ArgType::Unknown(size) => format!("Unknown({size})"),
+        other => format!("{other:?}"),
+    }
+}
+
+/// The instruction written the way it is used, with the names and types of its args
+fn signature(instruction: &GenericInstruction) -> String {
+    let arg_info = instruction.arg_info();
+    let args: Vec<String> = instruction
+        .all_args()
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| match arg_info.get(index) {
+            Some(info) if !info.name.is_empty() => format!("{}={}", info.name, arg_type_name(arg)),
+            _ => arg_type_name(arg),
+        })
+        .collect();
+
+    format!("{}: {}", instruction.display_name(), args.join(", "))
+}
+
+fn markdown(value: String) -> Documentation {
+    Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    })
+}
+
+fn completions(index: &ConfigIndex, text: &str, position: Position) -> Vec<CompletionItem> {
+    let Some((masked, original, column)) = line_at(text, position) else {
+        return Vec::new();
+    };
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    if mask_comments(&text[..line_start + column]).1 {
+        return Vec::new();
+    }
+    let prefix = &masked[..column];
+
+    let edit = |range: Range<usize>, new_text: String| {
+        Some(CompletionTextEdit::Edit(TextEdit::new(
+            lsp_range(original, position.line, range),
+            new_text,
+        )))
+    };
+
+    let Some(scanned) = scan_line(prefix) else {
+        let name_start = prefix.len() - prefix.trim_start().len();
+
+        return index
+            .by_id
+            .values()
+            .map(|instruction| {
+                let name = instruction.display_name();
+                CompletionItem {
+                    label: name.clone(),
+                    kind: Some(CompletionItemKind::FUNCTION),
+                    detail: Some(signature(instruction)),
+                    documentation: Some(markdown(instruction.description().to_string())),
+                    text_edit: edit(name_start..column, name),
+                    ..Default::default()
+                }
+            })
+            .collect();
+    };
+
+    let current = scanned
+        .args
+        .last()
+        .expect("scanned lines always have an arg");
+
+    if let Some(variable) = current.value.strip_prefix("Mem(") {
+        if variable.contains(')') {
+            return Vec::new();
+        }
+        let variables: BTreeMap<_, _> = index.config.named_variables.iter().collect();
+
+        return variables
+            .into_iter()
+            .map(|(id, name)| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail: Some(format!("Variable ID {id}")),
+                text_edit: edit(current.value_start + "Mem(".len()..column, name.clone()),
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    let Some(info) = index.instruction(scanned.name) else {
+        return Vec::new();
+    };
+    let arg_info = info.arg_info();
+    let mut items = Vec::new();
+
+    let position_index = scanned.arg_position(scanned.args.len() - 1, info);
+    if let Some(ArgType::Enum(enum_name)) =
+        position_index.and_then(|i| info.all_args().get(i).cloned())
+    {
+        if let Some(variants) = index.config.named_value_maps.get(&enum_name) {
+            let variants: BTreeMap<_, _> = variants.iter().collect();
+            items.extend(variants.into_iter().map(|(value, variant)| CompletionItem {
+                label: format!("({variant})"),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some(format!("{enum_name} value {value}")),
+                text_edit: edit(current.value_start..column, format!("({variant})")),
+                ..Default::default()
+            }));
+        }
+    }
+
+    // args can also be given by name while no value has been typed past a possible name
+    let typing_name = current.name.is_none()
+        && current
+            .value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if typing_name {
+        let given: Vec<usize> = (0..scanned.args.len() - 1)
+            .filter_map(|index| scanned.arg_position(index, info))
+            .collect();
+        let args = info.all_args();
+        items.extend(
+            arg_info
+                .iter()
+                .zip(args.iter())
+                .enumerate()
+                .filter(|(position, (arg, _))| !arg.name.is_empty() && !given.contains(position))
+                .map(|(_, (arg, arg_type))| CompletionItem {
+                    label: format!("{}=", arg.name),
+                    kind: Some(CompletionItemKind::FIELD),
+                    detail: Some(arg_type_name(arg_type)),
+                    documentation: Some(markdown(arg.description.clone())),
+                    text_edit: edit(current.value_start..column, format!("{}=", arg.name)),
+                    ..Default::default()
+                }),
+        );
+    }
+
+    items
+}
+
+fn hover(index: &ConfigIndex, text: &str, position: Position) -> Option<Hover> {
+    let (masked, original, column) = line_at(text, position)?;
+    let scanned = scan_line(&masked)?;
+    let info = index.instruction(scanned.name)?;
+
+    let (contents, range) = if scanned.name_span().contains(&column) {
+        (instruction_docs(info), scanned.name_span())
+    } else {
+        let arg_index = scanned.args.iter().position(|arg| {
+            !arg.span.is_empty() && (arg.span.start..=arg.span.end).contains(&column)
+        })?;
+        let arg = &scanned.args[arg_index];
+        let position = scanned.arg_position(arg_index, info)?;
+
+        (
+            arg_docs(index, info, position, arg.value)?,
+            arg.span.clone(),
+        )
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: contents,
+        }),
+        range: Some(lsp_range(original, position.line, range)),
+    })
+}
+
+fn instruction_docs(info: &GenericInstruction) -> String {
+    let mut docs = format!("```\n{}\n```\n\nID {}", signature(info), info.id());
+    if let Some(size) = info.size() {
+        docs += &format!(", {size} bytes");
+    }
+    if !info.description().is_empty() {
+        docs += &format!("\n\n{}", info.description());
+    }
+
+    let arg_info = info.arg_info();
+    for (index, arg) in info.all_args().iter().enumerate() {
+        docs += &format!("\n- arg {index}");
+        if let Some(info) = arg_info.get(index) {
+            if !info.name.is_empty() {
+                docs += &format!(" `{}`", info.name);
+            }
+            docs += &format!(": {}", arg_type_name(arg));
+            if !info.description.is_empty() {
+                docs += &format!(", {}", info.description);
+            }
+        } else {
+            docs += &format!(": {}", arg_type_name(arg));
+        }
+    }
+
+    docs
+}
+
+fn arg_docs(
+    index: &ConfigIndex,
+    info: &GenericInstruction,
+    position: usize,
+    value: &str,
+) -> Option<String> {
+    let arg_type = info.all_args().get(position)?.clone();
+    let arg_info = info.arg_info().get(position);
+
+    let mut docs = format!("arg {position}");
+    if let Some(name) = arg_info
+        .map(|arg| &arg.name)
+        .filter(|name| !name.is_empty())
+    {
+        docs += &format!(" `{name}`");
+    }
+    docs += &format!(": {}", arg_type_name(&arg_type));
+    if let Some(description) = arg_info
+        .map(|arg| &arg.description)
+        .filter(|d| !d.is_empty())
+    {
+        docs += &format!("\n\n{description}");
+    }
+
+    let config = &index.config;
+    match arg_type {
+        ArgType::Enum(enum_name) => {
+            let variants = config.named_value_maps.get(&enum_name);
+            let variant = value.strip_prefix('(').and_then(|v| v.strip_suffix(')'));
+            let meaning = match (variant, value.parse::<i32>()) {
+                (Some(variant), _) => variants
+                    .and_then(|map| map.get_by_right(variant))
+                    .map(|number| format!("`{variant}` is {number}")),
+                (None, Ok(number)) => variants
+                    .and_then(|map| map.get_by_left(&number))
+                    .map(|variant| format!("{number} is `{variant}`")),
+                _ => None,
+            };
+            if let Some(meaning) = meaning {
+                docs += &format!("\n\n{meaning} in `{enum_name}`");
+            }
+        }
+        ArgType::AccessedValue => {
+            let variable = value.strip_prefix("Mem(").and_then(|v| v.strip_suffix(')'));
+            let meaning = match variable.map(|v| (v, v.parse::<i32>())) {
+                Some((_, Ok(id))) => config
+                    .named_variables
+                    .get_by_left(&id)
+                    .map(|name| format!("Variable {id} is `{name}`")),
+                Some((name, Err(_))) => config
+                    .named_variables
+                    .get_by_right(name)
+                    .map(|id| format!("Variable `{name}` has ID {id}")),
+                None => None,
+            };
+            if let Some(meaning) = meaning {
+                docs += &format!("\n\n{meaning}");
+            }
+        }
+        _ => {}
+    }
+
+    Some(docs)
+}
+
+/// The kind and name of the symbol referenced by the instruction at `position`
+fn referenced_symbol(
+    index: &ConfigIndex,
+    text: &str,
+    position: Position,
+) -> Option<(SymbolKind, String)> {
+    let (masked, _, column) = line_at(text, position)?;
+    let scanned = scan_line(&masked)?;
+
+    let Symbol::References(kind) = index.instruction(scanned.name)?.symbol() else {
+        return None;
+    };
+    // the name under the cursor, or the first one when the cursor is elsewhere in the line
+    let names: Vec<_> = scanned.symbol_names().collect();
+    let (name, _) = names
+        .iter()
+        .find(|(_, span)| span.start <= column && column <= span.end)
+        .or(names.first())?;
+
+    Some((kind, name.clone()))
+}
+
+/// Finds where a symbol is defined in `text`. Labels are only found in the top level block containing `from_line`
+fn find_definition(
+    index: &ConfigIndex,
+    text: &str,
+    kind: SymbolKind,
+    name: &str,
+    from_line: u32,
+) -> Option<lsp_types::Range> {
+    let (masked, _) = mask_comments(text);
+
+    let mut depth = 0usize;
+    let mut block = 0usize;
+    let mut from_block = None;
+    let mut definitions = Vec::new();
+    for (line_number, (line, original)) in lines(&masked).zip(lines(text)).enumerate() {
+        let info = scan_line(line)
+            .and_then(|scanned| index.instruction(scanned.name).map(|info| (scanned, info)));
+
+        if let Some((_, info)) = &info {
+            if info.block_type() == CodeBlock::Begin {
+                if depth == 0 {
+                    block += 1;
+                }
+                depth += 1;
+            }
+        }
+        if line_number == from_line as usize {
+            from_block = Some(block);
+        }
+
+        let Some((scanned, info)) = info else {
+            continue;
+        };
+        if info.symbol() == Symbol::Defines(kind) {
+            for (defined, span) in scanned.symbol_names() {
+                if defined == name {
+                    let range = lsp_range(original, line_number as u32, span);
+                    definitions.push((block, range));
+                }
+            }
+        }
+        if info.block_type() == CodeBlock::End {
+            depth = depth.saturating_sub(1);
+        }
+    }
+
+    definitions
+        .into_iter()
+        .find(|(block, _)| kind != SymbolKind::Label || Some(*block) == from_block)
+        .map(|(_, range)| range)
+}
+
+/// Rebuilds the script to find the errors the rebuilder would report, such as unknown instructions,
+/// args of the wrong size and unknown enum variants
+fn diagnostics(config: &ScriptConfig, text: &str) -> Vec<Diagnostic> {
+    match rebuild_bbscript_unchecked::<LittleEndian>(config, text.to_string()) {
+        Ok(_) => Vec::new(),
+        Err(error) => {
+            let mut diagnostics = Vec::new();
+            collect_diagnostics(text, error, &mut diagnostics);
+            diagnostics
+        }
+    }
+}
+
+fn collect_diagnostics(text: &str, error: BBScriptError, diagnostics: &mut Vec<Diagnostic>) {
+    let diagnostic = |range: lsp_types::Range, message: String| Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("bbscript".into()),
+        message,
+        ..Default::default()
+    };
+
+    match error {
+        BBScriptError::Multiple(errors) => {
+            for error in errors {
+                collect_diagnostics(text, error, diagnostics);
+            }
+        }
+        BBScriptError::Spanned(inner, span) => {
+            let range = char_range(&span.source_line, span.column, span.len);
+            let range = lsp_range(&span.source_line, span.line as u32 - 1, range);
+            diagnostics.push(diagnostic(range, inner.to_string()));
+        }
+        BBScriptError::PestConsumeError(error) => {
+            let (line, column) = match error.line_col {
+                pest::error::LineColLocation::Pos(position) => position,
+                pest::error::LineColLocation::Span(start, _) => start,
+            };
+            let line_text = lines(text).nth(line - 1).unwrap_or_default();
+            let range = lsp_range(line_text, line as u32 - 1, char_range(line_text, column, 1));
+            diagnostics.push(diagnostic(range, error.variant.message().into_owned()));
+        }
+        error => diagnostics.push(diagnostic(lsp_types::Range::default(), error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{CompletionTextEdit, HoverContents, Position};
+
+    use super::{completions, diagnostics, find_definition, hover, ConfigIndex};
+    use crate::game_config::test::config;
+    use crate::game_config::SymbolKind;
+
+    fn index() -> ConfigIndex {
+        ConfigIndex::new(config())
+    }
+
+    fn labels(index: &ConfigIndex, text: &str, line: u32, character: u32) -> Vec<String> {
+        completions(index, text, Position::new(line, character))
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    fn complete() {
+        let index = index();
+
+        let names = labels(&index, "beginState: s32'A'\n  tu", 1, 4);
+        assert!(names.contains(&"turn".to_string()));
+        assert!(names.contains(&"beginSubroutine".to_string()));
+
+        let text = "beginState: s32'A'\n  turn: 1, ";
+        assert_eq!(labels(&index, text, 1, 11), ["(Left)", "(Right)", "to="]);
+
+        let items = completions(&index, "  turn: 1, (Ri", Position::new(0, 14));
+        let Some(CompletionTextEdit::Edit(edit)) = &items[1].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(
+            (edit.range.start.character, edit.range.end.character),
+            (11, 14)
+        );
+        assert_eq!(edit.new_text, "(Right)");
+
+        // named args are completed at the position of their name
+        assert_eq!(labels(&index, "  turn: to=(", 0, 12), ["(Left)", "(Right)"]);
+        assert_eq!(
+            labels(&index, "  turn: 1, (Left), Mem(H", 0, 24),
+            ["Tmp", "Hits"]
+        );
+
+        assert!(labels(&index, "  turn: 1 // (", 0, 14).is_empty());
+        assert!(labels(&index, "/* turn: 1,\n  turn: 1, ", 1, 11).is_empty());
+    }
+
+    #[test]
+    fn hover_docs() {
+        let index = index();
+        let text = "  turn: 1, to=(Right), Mem(Hits)";
+
+        let HoverContents::Markup(docs) =
+            hover(&index, text, Position::new(0, 3)).unwrap().contents
+        else {
+            panic!("expected markdown");
+        };
+        assert_eq!(
+            docs.value,
+            "```\nturn: speed=Number, to=Direction, AccessedValue\n```\n\n\
+            ID 7, 20 bytes\n\nTurns <around> | back\n\
+            - arg 0 `speed`: Number\n\
+            - arg 1 `to`: Direction, Side to face\n\
+            - arg 2: AccessedValue"
+        );
+
+        let hovered = hover(&index, text, Position::new(0, 16)).unwrap();
+        let HoverContents::Markup(docs) = hovered.contents else {
+            panic!("expected markdown");
+        };
+        assert_eq!(
+            docs.value,
+            "arg 1 `to`: Direction\n\nSide to face\n\n`Right` is 1 in `Direction`"
+        );
+        let range = hovered.range.unwrap();
+        assert_eq!((range.start.character, range.end.character), (11, 21));
+
+        let HoverContents::Markup(docs) =
+            hover(&index, text, Position::new(0, 25)).unwrap().contents
+        else {
+            panic!("expected markdown");
+        };
+        assert_eq!(
+            docs.value,
+            "arg 2: AccessedValue\n\nVariable `Hits` has ID 47"
+        );
+    }
+
+    #[test]
+    fn go_to_definition() {
+        let index = index();
+        let text = "beginState: s32'A'\n  \
+            label: s16'loop'\n  \
+            callSubroutine: s32'sub'\n  \
+            gotoLabel: s16'loop'\n\
+            endState:\n\
+            beginSubroutine: s32'sub'\n  \
+            // label: s16'loop'\n  \
+            label: s16'loop'\n\
+            endSubroutine:";
+
+        let range = find_definition(&index, text, SymbolKind::Subroutine, "sub", 2).unwrap();
+        assert_eq!(range.start, Position::new(5, 17));
+        assert_eq!(range.end, Position::new(5, 25));
+
+        // labels are local to the top level block they are defined in
+        let range = find_definition(&index, text, SymbolKind::Label, "loop", 3).unwrap();
+        assert_eq!(range.start.line, 1);
+        let range = find_definition(&index, text, SymbolKind::Label, "loop", 8).unwrap();
+        assert_eq!(range.start.line, 7);
+
+        assert!(find_definition(&index, text, SymbolKind::State, "sub", 0).is_none());
+    }
+
+    #[test]
+    fn report_diagnostics() {
+        let index = index();
+
+        let text = "beginState: s32'A'\n  turn: 1, (Up), Val(0)\nendState:";
+        let found = diagnostics(&index.config, text);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].message,
+            "No value associated with variant `Up` in enum `Direction`"
+        );
+        assert_eq!(found[0].range.start, Position::new(1, 11));
+        assert_eq!(found[0].range.end, Position::new(1, 15));
+
+        let found = diagnostics(
+            &index.config,
+            "beginState: s32'A'\n  turn: 1,, 2\nendState:",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].range.start.line, 1);
+
+        let text = "beginState: s32'A'\n  turn: 1, (Left), Val(0)\nendState:";
+        assert!(diagnostics(&index.config, text).is_empty());
+        // enum variants can't be written for instructions missing from the config
+        let text = "beginState: s32'A'\n  Unknown99999: (Left)\nendState:";
+        let found = diagnostics(&index.config, text);
+        assert_eq!(found.len(), 1);
+        assert!(found[0]
+            .message
+            .starts_with("Enum variant `Left` can't be used"));
+        assert_eq!(found[0].range.start, Position::new(1, 16));
+        assert_eq!(found[0].range.end, Position::new(1, 22));
+    }
+}
@@ -1,71 +1,24 @@
-mod error;
-mod game_config;
-mod parser;
-mod rebuilder;
+mod batch;
 
 use anyhow::Result as AResult;
+use bbscript::framedata::{frame_data_to_string, FrameDataFormat, FrameDataMarkers};
+use bbscript::infer::{ArgInferrer, SizeInferrer};
+use bbscript::lint::{LintConfig, LintLevel};
+use bbscript::parser::SkippedBytes;
+use bbscript::{
+    rebuild_from_format, verify_round_trip, BBScriptError, ConfigOverlay, Container, DocsFormat,
+    ExternalSymbols, ScriptConfig, ScriptFormat, SupportedGame, TextOptions,
+};
 use clap::{crate_version, Args, Parser, Subcommand, ValueEnum};
-use game_config::ScriptConfig;
-
-extern crate pest_derive;
 
 use std::fs::{metadata, File};
 use std::io::prelude::*;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-use crate::error::BBScriptError;
+use crate::batch::{collect_files, collect_jobs, run_jobs};
 #[cfg(feature = "old-cfg-converter")]
-use crate::game_config::GameDB;
-use crate::rebuilder::rebuild_bbscript;
-
-type HashMap<K, V> = std::collections::HashMap<K, V>;
-
-const BBCF_CONFIG: &str = include_str!("../static_db/bbcf.ron");
-const DBFZ_CONFIG: &str = include_str!("../static_db/dbfz.ron");
-const DNF_CONFIG: &str = include_str!("../static_db/dnf.ron");
-const GBVS_CONFIG: &str = include_str!("../static_db/gbvs.ron");
-const GBVSR_CONFIG: &str = include_str!("../static_db/gbvsr.ron");
-const GGREV2_CONFIG: &str = include_str!("../static_db/ggrev2.ron");
-const GGST_CONFIG: &str = include_str!("../static_db/ggst.ron");
-const P4U2_CONFIG: &str = include_str!("../static_db/p4u2.ron");
-
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum SupportedGame {
-    /// Blazblue: Centralfiction
-    Bbcf,
-    /// Dragon Ball FighterZ
-    Dbfz,
-    /// DNF Duel
-    Dnf,
-    /// Granblue Fantasy Versus
-    Gbvs,
-    /// Granblue Fantasy Versus: Rising
-    Gbvsr,
-    /// Guilty Gear Xrd Rev2
-    Ggrev2,
-    /// Guilty Gear Strive
-    Ggst,
-    /// Persona 4 Arena Ultimax
-    P4u2,
-}
-
-impl SupportedGame {
-    pub fn into_config(self) -> ScriptConfig {
-        let result = match self {
-            SupportedGame::Bbcf => ScriptConfig::new(BBCF_CONFIG.as_bytes()),
-            SupportedGame::Dbfz => ScriptConfig::new(DBFZ_CONFIG.as_bytes()),
-            SupportedGame::Dnf => ScriptConfig::new(DNF_CONFIG.as_bytes()),
-            SupportedGame::Gbvs => ScriptConfig::new(GBVS_CONFIG.as_bytes()),
-            SupportedGame::Gbvsr => ScriptConfig::new(GBVSR_CONFIG.as_bytes()),
-            SupportedGame::Ggrev2 => ScriptConfig::new(GGREV2_CONFIG.as_bytes()),
-            SupportedGame::Ggst => ScriptConfig::new(GGST_CONFIG.as_bytes()),
-            SupportedGame::P4u2 => ScriptConfig::new(P4U2_CONFIG.as_bytes()),
-        };
-
-        // all embedded configs should parse correctly so this should be infallible
-        result.unwrap()
-    }
-}
+use bbscript::game_config::GameDB;
 
 fn main() {
     if let Err(e) = run() {
@@ -90,8 +43,17 @@ struct MainCli {
 }
 
 #[derive(Args, Debug, Clone)]
-#[group(required = true, multiple = false)]
 struct ConfigArgs {
+    #[clap(flatten)]
+    base: BaseConfigArgs,
+    /// Overlay file applied on top of the config, which can be given multiple times to stack overlays in order
+    #[arg(long, value_name = "OVERLAY")]
+    overlay: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+#[group(required = true, multiple = false)]
+struct BaseConfigArgs {
     /// A game supported by BBScript internally
     #[arg(short, long, group = "game-config")]
     game: Option<SupportedGame>,
@@ -108,10 +70,10 @@ enum SubCmd {
         /// File name of a config within the game DB folder
         #[clap(name = "GAME", flatten)]
         game: ConfigArgs,
-        /// BBScript file to parse into readable format
-        #[clap(name = "INPUT")]
-        input: PathBuf,
-        /// File to write readable script to as output
+        /// BBScript files, directories or glob patterns to parse into readable format
+        #[clap(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// File to write readable script to as output, or a directory when parsing multiple files
         #[clap(name = "OUTPUT")]
         output: PathBuf,
         /// Enables overwriting the file if a file with the same name as OUTPUT already exists
@@ -125,16 +87,220 @@ enum SubCmd {
         end_offset: Option<usize>,
         #[arg(short, long, default_value_t = 12)]
         indent_limit: usize,
+        /// Format to write the parsed script in
+        #[arg(short, long, value_enum, default_value_t = ScriptFormat::Text)]
+        format: ScriptFormat,
+        /// Disables detecting and keeping a container such as an Unreal header around the script
+        #[arg(short, long)]
+        raw: bool,
+        /// Skips over instructions missing from a sized config instead of failing,
+        /// keeping their bytes as a raw `Unknown` blob and listing the unknown IDs
+        #[arg(long)]
+        recover: bool,
+        /// Writes args that are named in the config as `name=value`
+        #[arg(long)]
+        keyword_args: bool,
+        /// Writes instruction descriptions and the meaning of enum args from the config as `//` comments
+        #[arg(long)]
+        comments: bool,
     },
     /// Rebuilds readable BBScript into BBScript usable by games
     Rebuild {
         /// File name of a config within the game DB folder
         #[clap(flatten)]
         game: ConfigArgs,
-        /// Readable script to use as input
-        #[arg(name = "INPUT")]
-        input: PathBuf,
-        /// File to write rebuilt script to as output
+        /// Readable scripts, directories or glob patterns to use as input
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// File to write rebuilt script to as output, or a directory when rebuilding multiple files
+        #[arg(name = "OUTPUT")]
+        output: PathBuf,
+        /// Enables overwriting the file if a file with the same name as OUTPUT already exists
+        #[arg(short, long)]
+        overwrite: bool,
+        /// Format the input scripts are written in
+        #[arg(short, long, value_enum, default_value_t = ScriptFormat::Text)]
+        format: ScriptFormat,
+        /// Disables restoring the container saved alongside an input when it was parsed
+        #[arg(short, long)]
+        raw: bool,
+        /// Readable scripts, such as common scripts, whose states and subroutines can be referenced by INPUT
+        #[arg(long, value_name = "SCRIPT")]
+        symbols_from: Vec<PathBuf>,
+    },
+    /// Rewrites readable scripts in place in the layout written by `parse`, keeping their comments
+    Fmt {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// Readable scripts, directories or glob patterns to format
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        #[arg(short, long, default_value_t = 12)]
+        indent_limit: usize,
+        /// Lists the scripts that are not formatted instead of rewriting them
+        #[arg(long)]
+        check: bool,
+    },
+    /// Checks readable scripts for mistakes that rebuild, but are unlikely to be intended
+    Lint {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// Readable scripts, directories or glob patterns to lint
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// RON file setting lints to `Allow`, `Warn` or `Deny`, such as `(unused_label: Allow)`
+        #[arg(short, long, value_name = "FILE")]
+        lints: Option<PathBuf>,
+    },
+    /// Parses and rebuilds BBScript files, checking that the rebuilt file is identical to the original
+    Verify {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// BBScript files, directories or glob patterns to verify
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// Takes a hex offset from the start of the file specifying where the script actually begins
+        #[arg(short, long, value_parser(parse_hex))]
+        start_offset: Option<usize>,
+        /// Takes a hex offset from the end of the file specifying where the script actually ends
+        #[clap(short, long, value_parser(parse_hex))]
+        end_offset: Option<usize>,
+        /// Disables detecting a container such as an Unreal header around the script
+        #[arg(short, long)]
+        raw: bool,
+    },
+    /// Infers the sizes of instructions missing from a sized config using scripts that contain them,
+    /// printing candidate config entries in RON
+    InferSizes {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// BBScript files, directories or glob patterns to search for unknown instructions
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// Takes a hex offset from the start of the file specifying where the script actually begins
+        #[arg(short, long, value_parser(parse_hex))]
+        start_offset: Option<usize>,
+        /// Takes a hex offset from the end of the file specifying where the script actually ends
+        #[clap(short, long, value_parser(parse_hex))]
+        end_offset: Option<usize>,
+        /// Disables detecting a container such as an Unreal header around the script
+        #[arg(short, long)]
+        raw: bool,
+    },
+    /// Suggests types for args that a config only knows as unknown bytes, using scripts that contain them,
+    /// printing a patch of the config's instructions in RON
+    InferArgs {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// BBScript files, directories or glob patterns to search for unknown args
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// Takes a hex offset from the start of the file specifying where the script actually begins
+        #[arg(short, long, value_parser(parse_hex))]
+        start_offset: Option<usize>,
+        /// Takes a hex offset from the end of the file specifying where the script actually ends
+        #[clap(short, long, value_parser(parse_hex))]
+        end_offset: Option<usize>,
+        /// Disables detecting a container such as an Unreal header around the script
+        #[arg(short, long)]
+        raw: bool,
+    },
+    /// Writes the startup, active and recovery frames of each state in BBScript files,
+    /// along with values such as attack level and blockstun
+    #[command(name = "framedata")]
+    FrameData {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// BBScript files, directories or glob patterns to find the frame data of
+        #[arg(name = "INPUT", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// File to write frame data to, or a directory when reading multiple files
+        #[arg(name = "OUTPUT")]
+        output: PathBuf,
+        /// Enables overwriting the file if a file with the same name as OUTPUT already exists
+        #[arg(short, long)]
+        overwrite: bool,
+        /// Takes a hex offset from the start of the file specifying where the script actually begins
+        #[arg(short, long, value_parser(parse_hex))]
+        start_offset: Option<usize>,
+        /// Takes a hex offset from the end of the file specifying where the script actually ends
+        #[clap(short, long, value_parser(parse_hex))]
+        end_offset: Option<usize>,
+        /// Disables detecting a container such as an Unreal header around the script
+        #[arg(short, long)]
+        raw: bool,
+        /// Format to write frame data in
+        #[arg(short, long, value_enum, default_value_t = FrameDataFormat::Csv)]
+        format: FrameDataFormat,
+        /// RON file naming the instructions that mark active and recovery frames and the values to collect,
+        /// such as `(hit_active: ["hit"], recovery: ["recoveryState"])`
+        #[arg(short, long, value_name = "FILE")]
+        markers: Option<PathBuf>,
+    },
+    /// Renders a config as a browsable reference of its instructions, args, enums and named variables
+    Docs {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+        /// File to write the reference to
+        #[arg(name = "OUTPUT")]
+        output: PathBuf,
+        /// Enables overwriting the file if a file with the same name as OUTPUT already exists
+        #[arg(short, long)]
+        overwrite: bool,
+        /// Format to write the reference in
+        #[arg(short, long, value_enum, default_value_t = DocsFormat::Markdown)]
+        format: DocsFormat,
+    },
+    /// Runs a language server for readable scripts over stdin and stdout, for use by text editors
+    Lsp {
+        /// File name of a config within the game DB folder
+        #[clap(flatten)]
+        game: ConfigArgs,
+    },
+    /// Compares and merges configs, such as those of different versions of a game
+    Config {
+        #[command(subcommand)]
+        command: ConfigCmd,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCmd {
+    /// Shows the instructions, enums and named variables added, removed or changed between two configs
+    Diff {
+        /// Name of a game supported by BBScript internally, or path of a config file
+        #[arg(name = "OLD", value_parser(parse_config_source))]
+        old: ConfigSource,
+        /// Name of a game supported by BBScript internally, or path of a config file
+        #[arg(name = "NEW", value_parser(parse_config_source))]
+        new: ConfigSource,
+    },
+    /// Checks a config for problems that would otherwise only show up when parsing or rebuilding scripts
+    Check {
+        /// Name of a game supported by BBScript internally, or path of a config file
+        #[arg(name = "CONFIG", value_parser(parse_config_source))]
+        config: ConfigSource,
+        /// Overlay file applied on top of the config, which can be given multiple times to stack overlays in order
+        #[arg(long, value_name = "OVERLAY")]
+        overlay: Vec<PathBuf>,
+    },
+    /// Carries names, descriptions and enums from an old config onto instructions of a new config
+    /// with the same ID and arg layout, writing the merged config to OUTPUT
+    Merge {
+        /// Name of a game supported by BBScript internally, or path of a config file to carry names from
+        #[arg(name = "OLD", value_parser(parse_config_source))]
+        old: ConfigSource,
+        /// Name of a game supported by BBScript internally, or path of a config file to carry names onto
+        #[arg(name = "NEW", value_parser(parse_config_source))]
+        new: ConfigSource,
+        /// File to write the merged config to
         #[arg(name = "OUTPUT")]
         output: PathBuf,
         /// Enables overwriting the file if a file with the same name as OUTPUT already exists
@@ -146,7 +312,11 @@ enum SubCmd {
 fn run() -> AResult<()> {
     let args = MainCli::parse();
 
-    let level = log_level_from_verbosity(args.verbosity);
+    // the language server talks to its client over stdout, which the logger would write to as well
+    let level = match args.command {
+        SubCmd::Lsp { .. } => log::LevelFilter::Off,
+        _ => log_level_from_verbosity(args.verbosity),
+    };
     simple_logger::SimpleLogger::new()
         .with_level(level)
         .without_timestamps()
@@ -155,43 +325,344 @@ fn run() -> AResult<()> {
     match args.command {
         SubCmd::Parse {
             game,
-            input,
+            inputs,
             output,
             overwrite,
             start_offset,
             end_offset,
             indent_limit,
+            format,
+            raw,
+            recover,
+            keyword_args,
+            comments,
         } => {
-            confirm_io_files(&input, &output, overwrite)?;
+            let options = ParseOptions {
+                location: ScriptLocation::new(start_offset, end_offset, raw),
+                big_endian: args.big_endian,
+                text: TextOptions {
+                    indent_limit,
+                    keyword_args,
+                    comments,
+                },
+                format,
+                recover,
+            };
+            let parse_file = |config: &ScriptConfig, input: &Path, output: &Path| {
+                run_parser(config, input, output, options)
+            };
+
+            if is_batch(&inputs, &output) {
+                let jobs = collect_jobs(&inputs, &output)?;
+                let game = get_config(game)?;
+                run_jobs(&jobs, overwrite, |input, output| {
+                    parse_file(&game, input, output)
+                })?;
+            } else {
+                confirm_io_files(&inputs[0], &output, overwrite)?;
+                let game = get_config(game)?;
+                parse_file(&game, &inputs[0], &output)?;
+            }
+        }
+        SubCmd::Rebuild {
+            game,
+            inputs,
+            output,
+            overwrite,
+            format,
+            raw,
+            symbols_from,
+        } => {
+            let rebuild_file = |config: &ScriptConfig, external, input: &Path, output: &Path| {
+                run_rebuilder(
+                    config,
+                    input,
+                    output,
+                    args.big_endian,
+                    format,
+                    raw,
+                    external,
+                )
+            };
+
+            if is_batch(&inputs, &output) {
+                // sidecar containers are read alongside their scripts rather than rebuilt
+                let jobs = collect_jobs(&inputs, &output)?
+                    .into_iter()
+                    .filter(|job| !is_container_sidecar(&job.input))
+                    .collect::<Vec<_>>();
+                let game = get_config(game)?;
+                let external = load_external_symbols(&game, &symbols_from)?;
+                run_jobs(&jobs, overwrite, |input, output| {
+                    rebuild_file(&game, &external, input, output)
+                })?;
+            } else {
+                confirm_io_files(&inputs[0], &output, overwrite)?;
+                let game = get_config(game)?;
+                let external = load_external_symbols(&game, &symbols_from)?;
+                rebuild_file(&game, &external, &inputs[0], &output)?;
+            }
+        }
+        SubCmd::Fmt {
+            game,
+            inputs,
+            indent_limit,
+            check,
+        } => {
+            let files = collect_files(&inputs)?
+                .into_iter()
+                .map(|(input, _)| input)
+                .filter(|input| !is_container_sidecar(input))
+                .collect::<Vec<_>>();
             let game = get_config(game)?;
-            run_parser(
-                game,
-                input,
-                output,
-                (start_offset, end_offset),
+            run_fmt(&game, &files, indent_limit, check)?;
+        }
+        SubCmd::Lint {
+            game,
+            inputs,
+            lints,
+        } => {
+            let files = collect_files(&inputs)?
+                .into_iter()
+                .map(|(input, _)| input)
+                .filter(|input| !is_container_sidecar(input))
+                .collect::<Vec<_>>();
+            let lints = match lints {
+                Some(path) => LintConfig::load(path)?,
+                None => LintConfig::default(),
+            };
+            let game = get_config(game)?;
+            run_lint(&game, &files, &lints)?;
+        }
+        SubCmd::Verify {
+            game,
+            inputs,
+            start_offset,
+            end_offset,
+            raw,
+        } => {
+            let files = collect_files(&inputs)?
+                .into_iter()
+                .map(|(input, _)| input)
+                .filter(|input| !is_container_sidecar(input))
+                .collect::<Vec<_>>();
+            let config_name = config_name(&game);
+            let game = get_config(game)?;
+            run_verify(
+                &game,
+                &config_name,
+                &files,
+                ScriptLocation::new(start_offset, end_offset, raw),
                 args.big_endian,
-                indent_limit,
             )?;
         }
-        SubCmd::Rebuild {
+        SubCmd::InferSizes {
+            game,
+            inputs,
+            start_offset,
+            end_offset,
+            raw,
+        } => {
+            let files = collect_files(&inputs)?
+                .into_iter()
+                .map(|(input, _)| input)
+                .filter(|input| !is_container_sidecar(input))
+                .collect::<Vec<_>>();
+            let game = get_config(game)?;
+            run_infer_sizes(
+                &game,
+                &files,
+                ScriptLocation::new(start_offset, end_offset, raw),
+                args.big_endian,
+            )?;
+        }
+        SubCmd::InferArgs {
+            game,
+            inputs,
+            start_offset,
+            end_offset,
+            raw,
+        } => {
+            let files = collect_files(&inputs)?
+                .into_iter()
+                .map(|(input, _)| input)
+                .filter(|input| !is_container_sidecar(input))
+                .collect::<Vec<_>>();
+            let game = get_config(game)?;
+            run_infer_args(
+                &game,
+                &files,
+                ScriptLocation::new(start_offset, end_offset, raw),
+                args.big_endian,
+            )?;
+        }
+        SubCmd::FrameData {
             game,
-            input,
+            inputs,
             output,
             overwrite,
+            start_offset,
+            end_offset,
+            raw,
+            format,
+            markers,
         } => {
-            confirm_io_files(&input, &output, overwrite)?;
-            let game = get_config(game)?;
-            run_rebuilder(game, input, output, args.big_endian)?;
+            let markers = match markers {
+                Some(path) => FrameDataMarkers::load(path)?,
+                None => FrameDataMarkers::default(),
+            };
+            let location = ScriptLocation::new(start_offset, end_offset, raw);
+            let write_frame_data = |config: &ScriptConfig, input: &Path, output: &Path| {
+                run_frame_data(
+                    config,
+                    input,
+                    output,
+                    location,
+                    args.big_endian,
+                    &markers,
+                    format,
+                )
+            };
+
+            if is_batch(&inputs, &output) {
+                let extension = match format {
+                    FrameDataFormat::Csv => "csv",
+                    FrameDataFormat::Json => "json",
+                };
+                // each script gets its own file, named after it
+                let jobs = collect_jobs(&inputs, &output)?
+                    .into_iter()
+                    .filter(|job| !is_container_sidecar(&job.input))
+                    .map(|mut job| {
+                        job.output.set_extension(extension);
+                        job
+                    })
+                    .collect::<Vec<_>>();
+                let game = get_config(game)?;
+                run_jobs(&jobs, overwrite, |input, output| {
+                    write_frame_data(&game, input, output)
+                })?;
+            } else {
+                confirm_io_files(&inputs[0], &output, overwrite)?;
+                let game = get_config(game)?;
+                write_frame_data(&game, &inputs[0], &output)?;
+            }
+        }
+        SubCmd::Docs {
+            game,
+            output,
+            overwrite,
+            format,
+        } => {
+            if output.exists() && !overwrite {
+                return Err(
+                    BBScriptError::OutputAlreadyExists(output.to_string_lossy().into()).into(),
+                );
+            }
+            let title = format!("{} BBScript reference", config_name(&game));
+            let docs = get_config(game)?.render_docs(&title, format);
+            File::create(&output)?.write_all(docs.as_bytes())?;
         }
+        SubCmd::Lsp { game } => {
+            bbscript::lsp::run_stdio(get_config(game)?).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        SubCmd::Config { command } => match command {
+            ConfigCmd::Diff { old, new } => {
+                let diff = new.load()?.diff_from(&old.load()?);
+
+                if diff.is_empty() {
+                    println!("No differences found");
+                } else {
+                    print!("{diff}");
+                }
+            }
+            ConfigCmd::Check { config, overlay } => {
+                apply_overlays(config.load()?, &overlay)?.check()?;
+                println!("OK: no problems found");
+            }
+            ConfigCmd::Merge {
+                old,
+                new,
+                output,
+                overwrite,
+            } => {
+                if output.exists() && !overwrite {
+                    return Err(BBScriptError::OutputAlreadyExists(
+                        output.to_string_lossy().into(),
+                    )
+                    .into());
+                }
+                run_config_merge(&old.load()?, new.load()?, &output)?;
+            }
+        },
     }
     Ok(())
 }
 
-fn confirm_io_files(
-    input: &PathBuf,
-    output: &PathBuf,
-    overwrite: bool,
-) -> Result<(), BBScriptError> {
+/// Where the script is located within an input file
+#[derive(Debug, Clone, Copy)]
+enum ScriptLocation {
+    /// Hex offsets from the start and end of the file given by the user
+    Offsets(Option<usize>, Option<usize>),
+    /// Detect a container around the script, using the whole file if none is found
+    Detect,
+    /// The whole file is the script
+    Whole,
+}
+
+impl ScriptLocation {
+    fn new(start_offset: Option<usize>, end_offset: Option<usize>, raw: bool) -> Self {
+        match (start_offset, end_offset) {
+            (None, None) if raw => ScriptLocation::Whole,
+            (None, None) => ScriptLocation::Detect,
+            (start, end) => ScriptLocation::Offsets(start, end),
+        }
+    }
+
+    /// Splits a file into the container around the script and the range of the script itself
+    fn split(
+        self,
+        file: &[u8],
+        big_endian: bool,
+    ) -> Result<(Container, Range<usize>), BBScriptError> {
+        match self {
+            ScriptLocation::Offsets(start, end) => {
+                let (start, end) = (start.unwrap_or(0), end.unwrap_or(0));
+                let range = match file.len().checked_sub(end) {
+                    Some(script_end) if start <= script_end => start..script_end,
+                    _ => return Err(BBScriptError::BadScriptOffsets(start, end, file.len())),
+                };
+                Ok((Container::from_range(file, range.clone()), range))
+            }
+            ScriptLocation::Detect => {
+                let detected = if big_endian {
+                    Container::detect::<byteorder::BigEndian>(file)
+                } else {
+                    Container::detect::<byteorder::LittleEndian>(file)
+                };
+
+                Ok(detected.unwrap_or_else(|| (Container::default(), 0..file.len())))
+            }
+            ScriptLocation::Whole => Ok((Container::default(), 0..file.len())),
+        }
+    }
+}
+
+fn is_container_sidecar(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == bbscript::container::SIDECAR_EXTENSION)
+}
+
+/// Returns `true` if the inputs should be processed as a batch into an output directory
+/// rather than as a single input and output file
+fn is_batch(inputs: &[PathBuf], output: &Path) -> bool {
+    match inputs {
+        [input] => !input.is_file() || output.is_dir(),
+        _ => true,
+    }
+}
+
+fn confirm_io_files(input: &Path, output: &Path, overwrite: bool) -> Result<(), BBScriptError> {
     if Path::new(input).is_file() {
         if !Path::new(output).exists() || overwrite {
             Ok(())
@@ -206,16 +677,77 @@ fn confirm_io_files(
 }
 
 fn get_config(config_args: ConfigArgs) -> AResult<ScriptConfig> {
-    match (config_args.game, config_args.config_file) {
-        (Some(game), None) => Ok(game.into_config()),
-        (None, Some(path)) => Ok(ScriptConfig::load(path)?),
-        _ => panic!("this should never happen")
+    let config = match (config_args.base.game, config_args.base.config_file) {
+        (Some(game), None) => game.into_config(),
+        (None, Some(path)) => ScriptConfig::load(path)?,
+        _ => panic!("this should never happen"),
+    };
+
+    apply_overlays(config, &config_args.overlay)
+}
+
+/// Applies overlay files on top of a config in order
+fn apply_overlays(mut config: ScriptConfig, overlays: &[PathBuf]) -> AResult<ScriptConfig> {
+    for path in overlays {
+        config.apply_overlay(ConfigOverlay::load(path)?)?;
+    }
+
+    Ok(config)
+}
+
+/// A config given by either the name of a game supported internally or the path of a config file
+#[derive(Debug, Clone)]
+enum ConfigSource {
+    Game(SupportedGame),
+    File(PathBuf),
+}
+
+impl ConfigSource {
+    fn load(&self) -> AResult<ScriptConfig> {
+        match self {
+            ConfigSource::Game(game) => Ok(game.into_config()),
+            ConfigSource::File(path) => Ok(ScriptConfig::load(path)?),
+        }
     }
 }
 
+/// Existing files take precedence over games with the same name
+fn parse_config_source(input: &str) -> Result<ConfigSource, std::convert::Infallible> {
+    match SupportedGame::from_str(input, true) {
+        Ok(game) if !Path::new(input).is_file() => Ok(ConfigSource::Game(game)),
+        _ => Ok(ConfigSource::File(input.into())),
+    }
+}
+
+/// Name of the game or config file used, for display purposes
+fn config_name(config_args: &ConfigArgs) -> String {
+    match (config_args.base.game, &config_args.base.config_file) {
+        (Some(game), None) => game
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default(),
+        (None, Some(path)) => path.to_string_lossy().into(),
+        _ => panic!("this should never happen"),
+    }
+}
+
+/// Collects the states and subroutines defined in each readable script
+fn load_external_symbols(game: &ScriptConfig, scripts: &[PathBuf]) -> AResult<ExternalSymbols> {
+    let mut external = ExternalSymbols::default();
+
+    for path in scripts {
+        let mut script = String::new();
+        File::open(path)?.read_to_string(&mut script)?;
+        external.add_script(game, &script)?;
+    }
+
+    Ok(external)
+}
+
 /// Attempts to return a `Vec<u8>` of a files contents
-fn load_file(name: PathBuf) -> AResult<Vec<u8>> {
-    let mut file = File::open(&name)?;
+fn load_file(name: impl AsRef<Path>) -> AResult<Vec<u8>> {
+    let name = name.as_ref();
+    let mut file = File::open(name)?;
     let meta = metadata(name)?;
     let mut file_buf = Vec::with_capacity(meta.len() as usize);
 
@@ -242,34 +774,67 @@ fn log_level_from_verbosity(verbosity: u8) -> log::LevelFilter {
     }
 }
 
-fn run_parser(
-    game: ScriptConfig,
-    in_path: PathBuf,
-    out_path: PathBuf,
-    byte_range: (Option<usize>, Option<usize>),
+/// Settings shared by every file parsed by the `parse` subcommand
+#[derive(Clone, Copy)]
+struct ParseOptions {
+    location: ScriptLocation,
     big_endian: bool,
-    indent_limit: usize,
+    text: TextOptions,
+    format: ScriptFormat,
+    recover: bool,
+}
+
+fn run_parser(
+    game: &ScriptConfig,
+    in_path: &Path,
+    out_path: &Path,
+    options: ParseOptions,
 ) -> AResult<()> {
     let db = game;
+    let ParseOptions {
+        location,
+        big_endian,
+        text,
+        format,
+        recover,
+    } = options;
 
     let in_file = load_file(in_path)?;
 
-    let in_bytes = in_file;
-    let file_length = in_bytes.len();
-
-    let in_bytes =
-        in_bytes[byte_range.0.unwrap_or(0)..(file_length - byte_range.1.unwrap_or(0))].to_owned();
+    let (container, range) = location.split(&in_file, big_endian)?;
+    let script_start = range.start;
+    let in_bytes = &in_file[range];
 
-    let result = if big_endian {
-        db.parse_to_string::<byteorder::BigEndian>(in_bytes, indent_limit)
+    let result = if !recover {
+        if big_endian {
+            db.parse_to_format::<byteorder::BigEndian>(in_bytes, format, text)
+        } else {
+            db.parse_to_format::<byteorder::LittleEndian>(in_bytes, format, text)
+        }
     } else {
-        db.parse_to_string::<byteorder::LittleEndian>(in_bytes, indent_limit)
+        let (program, skipped) = if big_endian {
+            db.parse_recovering::<byteorder::BigEndian>(in_bytes)?
+        } else {
+            db.parse_recovering::<byteorder::LittleEndian>(in_bytes)?
+        };
+
+        report_skipped_bytes(in_path, script_start, &skipped);
+
+        let program = program
+            .into_iter()
+            .map(|(_, instruction)| instruction)
+            .collect::<Vec<_>>();
+        db.instructions_to_format(&program, format, text)
     };
 
     match result {
         Ok(f) => {
             let mut output = File::create(out_path)?;
             output.write_all(f.as_bytes())?;
+
+            if !container.is_empty() {
+                container.save(Container::sidecar_path(out_path))?;
+            }
         }
         Err(e) => return Err(e.into()),
     }
@@ -277,25 +842,70 @@ fn run_parser(
     Ok(())
 }
 
+/// Prints every region skipped while recovering, with offsets relative to the start of the input file,
+/// followed by the unknown IDs that caused them
+fn report_skipped_bytes(path: &Path, script_start: usize, skipped: &[SkippedBytes]) {
+    if skipped.is_empty() {
+        return;
+    }
+
+    for skip in skipped {
+        println!(
+            "SKIPPED: {}: {:#X} bytes at offset {:#X} starting with unknown instruction ID {}",
+            path.display(),
+            skip.len,
+            script_start + skip.offset,
+            skip.instruction_id,
+        );
+    }
+
+    let unknown_ids = skipped
+        .iter()
+        .map(|skip| skip.instruction_id)
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+
+    println!(
+        "UNKNOWN IDS: {}: {}",
+        path.display(),
+        unknown_ids.join(", ")
+    );
+}
+
 fn run_rebuilder(
-    game: ScriptConfig,
-    input: PathBuf,
-    output: PathBuf,
+    game: &ScriptConfig,
+    input: &Path,
+    output: &Path,
     big_endian: bool,
+    format: ScriptFormat,
+    raw: bool,
+    external: &ExternalSymbols,
 ) -> AResult<()> {
     let db = game;
 
     let mut script = String::new();
     File::open(input)?.read_to_string(&mut script)?;
 
-    let result = if big_endian {
-        rebuild_bbscript::<byteorder::BigEndian>(db, script)
+    let sidecar = Container::sidecar_path(input);
+    let container = if !raw && sidecar.is_file() {
+        Container::load(sidecar)?
     } else {
-        rebuild_bbscript::<byteorder::LittleEndian>(db, script)
+        Container::default()
     };
 
+    let result = if big_endian {
+        rebuild_from_format::<byteorder::BigEndian>(db, script, format, external)
+    } else {
+        rebuild_from_format::<byteorder::LittleEndian>(db, script, format, external)
+    }
+    .map_err(|e| e.with_file(input.to_string_lossy()));
+
     match result {
         Ok(f) => {
+            let f = container.wrap(&f);
+
             let mut output = File::create(output)?;
             output.write_all(&f)?;
         }
@@ -303,3 +913,318 @@ fn run_rebuilder(
     }
     Ok(())
 }
+
+/// Runs `process` on the script in each file, reporting files that can't be read or processed without stopping.
+/// Failures are printed to stderr so that results printed to stdout can be redirected into a file
+fn for_each_script<F>(files: &[PathBuf], location: ScriptLocation, big_endian: bool, mut process: F)
+where
+    F: FnMut(&[u8]) -> Result<(), BBScriptError>,
+{
+    for path in files {
+        let in_file = match load_file(path) {
+            Ok(in_file) => in_file,
+            Err(e) => {
+                eprintln!("FAILED: {}: {e}", path.display());
+                continue;
+            }
+        };
+        let range = match location.split(&in_file, big_endian) {
+            Ok((_, range)) => range,
+            Err(e) => {
+                eprintln!("FAILED: {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        if let Err(e) = process(&in_file[range]) {
+            eprintln!("FAILED: {}: {e}", path.display());
+        }
+    }
+}
+
+fn run_infer_sizes(
+    game: &ScriptConfig,
+    files: &[PathBuf],
+    location: ScriptLocation,
+    big_endian: bool,
+) -> AResult<()> {
+    let mut inferrer = SizeInferrer::new(game)?;
+
+    for_each_script(files, location, big_endian, |script| {
+        if big_endian {
+            inferrer.add_script::<byteorder::BigEndian>(game, script)
+        } else {
+            inferrer.add_script::<byteorder::LittleEndian>(game, script)
+        }
+    });
+
+    let mut entries = std::collections::BTreeMap::new();
+    for inference in inferrer.infer() {
+        match inference.to_instruction() {
+            Some(instruction) => {
+                entries.insert(inference.instruction_id, instruction);
+            }
+            None => eprintln!(
+                "UNRESOLVED: no size of instruction ID {} fits any of its {} occurrences",
+                inference.instruction_id, inference.occurrences
+            ),
+        }
+    }
+
+    eprintln!("inferred sizes for {} instructions", entries.len());
+    println!(
+        "{}",
+        ron::ser::to_string_pretty(&entries, ron::ser::PrettyConfig::default())?
+    );
+
+    Ok(())
+}
+
+fn run_infer_args(
+    game: &ScriptConfig,
+    files: &[PathBuf],
+    location: ScriptLocation,
+    big_endian: bool,
+) -> AResult<()> {
+    let mut inferrer = ArgInferrer::new();
+
+    for_each_script(files, location, big_endian, |script| {
+        if big_endian {
+            inferrer.add_script::<byteorder::BigEndian>(game, script)
+        } else {
+            inferrer.add_script::<byteorder::LittleEndian>(game, script)
+        }
+    });
+
+    let patch = if big_endian {
+        inferrer.patch::<byteorder::BigEndian>(game)
+    } else {
+        inferrer.patch::<byteorder::LittleEndian>(game)
+    };
+
+    println!(
+        "{}",
+        ron::ser::to_string_pretty(&patch, ron::ser::PrettyConfig::default())?
+    );
+
+    Ok(())
+}
+
+fn run_config_merge(old: &ScriptConfig, mut new: ScriptConfig, output: &Path) -> AResult<()> {
+    let report = new.merge_from(old);
+
+    for (id, name) in &report.name_conflicts {
+        println!("SKIPPED: instruction ID {id} can't be named `{name}`, another instruction already uses it");
+    }
+    for id in &report.layout_mismatches {
+        println!("SKIPPED: instruction ID {id} has a different arg layout");
+    }
+    for name in &report.unused_enums {
+        println!("SKIPPED: enum `{name}` isn't used by any instruction");
+    }
+
+    println!(
+        "carried {} names, {} descriptions, {} arg info lists, {} enum args, {} enums and {} named variables",
+        report.names.len(),
+        report.descriptions.len(),
+        report.arg_info.len(),
+        report.enum_args.len(),
+        report.enums.len(),
+        report.named_variables.len()
+    );
+
+    let merged = ron::ser::to_string_pretty(&new, ron::ser::PrettyConfig::default())?;
+    File::create(output)?.write_all(merged.as_bytes())?;
+
+    println!("OK: merged config written to {}", output.display());
+
+    Ok(())
+}
+
+fn run_frame_data(
+    game: &ScriptConfig,
+    in_path: &Path,
+    out_path: &Path,
+    location: ScriptLocation,
+    big_endian: bool,
+    markers: &FrameDataMarkers,
+    format: FrameDataFormat,
+) -> AResult<()> {
+    let in_file = load_file(in_path)?;
+    let (_, range) = location.split(&in_file, big_endian)?;
+
+    let tree = if big_endian {
+        game.parse_tree::<byteorder::BigEndian>(&in_file[range])?
+    } else {
+        game.parse_tree::<byteorder::LittleEndian>(&in_file[range])?
+    };
+
+    let data = game.frame_data(&tree, markers);
+    let out = frame_data_to_string(&data, markers, format)?;
+    File::create(out_path)?.write_all(out.as_bytes())?;
+
+    Ok(())
+}
+
+fn run_fmt(
+    game: &ScriptConfig,
+    files: &[PathBuf],
+    indent_limit: usize,
+    check: bool,
+) -> AResult<()> {
+    let mut unformatted = 0;
+    let mut failed = 0;
+    for path in files {
+        // a file that can't be read or written fails on its own rather than stopping the rest
+        let script = match std::fs::read_to_string(path) {
+            Ok(script) => script,
+            Err(e) => {
+                failed += 1;
+                println!("FAILED: {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        match game.format_script(&script, indent_limit) {
+            Ok(formatted) if formatted == script => println!("OK: {}", path.display()),
+            Ok(_) if check => {
+                unformatted += 1;
+                println!("UNFORMATTED: {}", path.display());
+            }
+            Ok(formatted) => {
+                match File::create(path).and_then(|mut f| f.write_all(formatted.as_bytes())) {
+                    Ok(()) => println!("FORMATTED: {}", path.display()),
+                    Err(e) => {
+                        failed += 1;
+                        println!("FAILED: {}: {e}", path.display());
+                    }
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "FAILED: {}: {}",
+                    path.display(),
+                    e.with_file(path.to_string_lossy())
+                );
+            }
+        }
+    }
+
+    let total = files.len();
+    if failed > 0 {
+        return Err(BBScriptError::BatchFailed(failed, total).into());
+    }
+    if unformatted > 0 {
+        return Err(BBScriptError::Unformatted(unformatted, total).into());
+    }
+
+    Ok(())
+}
+
+fn run_lint(game: &ScriptConfig, files: &[PathBuf], lints: &LintConfig) -> AResult<()> {
+    let mut denied = 0;
+    let mut failed = 0;
+    for path in files {
+        // a file that can't be read fails on its own rather than stopping the rest
+        let script = match std::fs::read_to_string(path) {
+            Ok(script) => script,
+            Err(e) => {
+                failed += 1;
+                println!("FAILED: {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        match game.lint_script(&script, lints) {
+            Ok(found) if found.is_empty() => println!("OK: {}", path.display()),
+            Ok(found) => {
+                if found.iter().any(|lint| lint.level == LintLevel::Deny) {
+                    denied += 1;
+                }
+                for lint in found {
+                    println!("{}\n", lint.with_file(path.to_string_lossy()));
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "FAILED: {}: {}",
+                    path.display(),
+                    e.with_file(path.to_string_lossy())
+                );
+            }
+        }
+    }
+
+    let total = files.len();
+    if failed > 0 {
+        return Err(BBScriptError::BatchFailed(failed, total).into());
+    }
+    if denied > 0 {
+        return Err(BBScriptError::LintsDenied(denied, total).into());
+    }
+
+    Ok(())
+}
+
+fn run_verify(
+    game: &ScriptConfig,
+    config_name: &str,
+    files: &[PathBuf],
+    location: ScriptLocation,
+    big_endian: bool,
+) -> AResult<()> {
+    let mut failed = 0;
+    for path in files {
+        // a file that can't be read fails on its own rather than stopping the rest
+        let in_file = match load_file(path) {
+            Ok(in_file) => in_file,
+            Err(e) => {
+                failed += 1;
+                println!("ERROR: {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        let range = match location.split(&in_file, big_endian) {
+            Ok((_, range)) => range,
+            Err(e) => {
+                failed += 1;
+                println!("ERROR: {}: {e}", path.display());
+                continue;
+            }
+        };
+        let in_bytes = &in_file[range];
+
+        let result = if big_endian {
+            verify_round_trip::<byteorder::BigEndian>(game, in_bytes)
+        } else {
+            verify_round_trip::<byteorder::LittleEndian>(game, in_bytes)
+        };
+
+        match result {
+            Ok(None) => println!("OK: {}", path.display()),
+            Ok(Some(mismatch)) => {
+                failed += 1;
+                println!("MISMATCH: {}: {mismatch}", path.display());
+            }
+            Err(e) => {
+                failed += 1;
+                println!("ERROR: {}: {e}", path.display());
+            }
+        }
+    }
+
+    let total = files.len();
+    println!(
+        "{config_name}: {} of {total} files round-trip cleanly",
+        total - failed
+    );
+
+    if failed > 0 {
+        return Err(BBScriptError::RoundTripFailed(failed, total).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::error::BBScriptError;
+use crate::game_config::{
+    ArgInfo, ArgType, BBSNumber, CodeBlock, InstructionInfo, ScriptConfig, SizedInstruction,
+    Symbol, UnsizedInstruction,
+};
+
+/// Changes to apply on top of a base config, such as one embedded for a [`crate::SupportedGame`].
+///
+/// Every field is optional, so an overlay only needs to list what it adds or overrides.
+/// Variables and enum variants replace any entry of the base config with the same value or name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigOverlay {
+    pub named_variables: BTreeMap<BBSNumber, String>,
+    pub named_value_maps: BTreeMap<String, BTreeMap<BBSNumber, String>>,
+    pub instructions: BTreeMap<u32, InstructionOverlay>,
+}
+
+/// Fields of an instruction to override, fields left out keep their value from the base config.
+///
+/// Instructions missing from a sized base config are added, and need a `size`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstructionOverlay {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_block: Option<CodeBlock>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<Symbol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<SmallVec<[ArgType; 16]>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arg_info: Option<Vec<ArgInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl ConfigOverlay {
+    /// Reads an overlay in RON, where optional fields can be written without `Some`
+    pub fn new<T: Read>(overlay: T) -> Result<Self, BBScriptError> {
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_reader(overlay)
+            .map_err(|e| BBScriptError::ConfigInvalid(e.to_string()))
+    }
+
+    pub fn load<T: AsRef<Path>>(overlay_path: T) -> Result<Self, BBScriptError> {
+        let overlay_file = File::open(&overlay_path).map_err(|e| {
+            BBScriptError::ConfigOpenError(
+                format!("{}", overlay_path.as_ref().display()),
+                e.to_string(),
+            )
+        })?;
+
+        Self::new(overlay_file)
+    }
+}
+
+impl ScriptConfig {
+    /// Applies an overlay on top of this config. Overlays applied later take precedence over earlier ones.
+    ///
+    /// Returns an error if the overlay adds a sized instruction without a size,
+    /// or leaves two instructions with the same name. The config is left unchanged when an error is returned
+    pub fn apply_overlay(&mut self, overlay: ConfigOverlay) -> Result<(), BBScriptError> {
+        let mut config = self.clone();
+        config.merge_overlay(overlay)?;
+        config.check_duplicate_names()?;
+
+        *self = config;
+        Ok(())
+    }
+
+    /// Applies the overlay without checking the resulting config
+    fn merge_overlay(&mut self, overlay: ConfigOverlay) -> Result<(), BBScriptError> {
+        for (value, name) in overlay.named_variables {
+            self.named_variables.insert(value, name);
+        }
+
+        for (enum_name, variants) in overlay.named_value_maps {
+            let map = self.named_value_maps.entry(enum_name).or_default();
+
+            for (value, variant) in variants {
+                map.insert(value, variant);
+            }
+        }
+
+        for (id, mut changes) in overlay.instructions {
+            match &mut self.instructions {
+                InstructionInfo::Sized(map) => {
+                    let instruction = match map.entry(id) {
+                        std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                        std::collections::hash_map::Entry::Vacant(entry) => {
+                            let size = changes.size.ok_or(BBScriptError::OverlayMissingSize(id))?;
+                            entry.insert(SizedInstruction::new(size))
+                        }
+                    };
+
+                    if let Some(size) = changes.size {
+                        instruction.size = size;
+                    }
+                    if let Some(args) = changes.args.take() {
+                        instruction.set_args(args);
+                    }
+                    changes.apply_to(
+                        &mut instruction.name,
+                        &mut instruction.code_block,
+                        &mut instruction.symbol,
+                        &mut instruction.arg_info,
+                        &mut instruction.description,
+                    );
+                }
+                InstructionInfo::Unsized(map) => {
+                    if changes.size.is_some() {
+                        log::warn!(
+                            "ignoring size of instruction ID {id} in overlay for an unsized config"
+                        );
+                    }
+
+                    let instruction = map.entry(id).or_insert_with(UnsizedInstruction::new);
+
+                    if let Some(args) = changes.args.take() {
+                        instruction.args = args;
+                    }
+                    changes.apply_to(
+                        &mut instruction.name,
+                        &mut instruction.code_block,
+                        &mut instruction.symbol,
+                        &mut instruction.arg_info,
+                        &mut instruction.description,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionOverlay {
+    /// Overrides the fields shared by sized and unsized instructions
+    fn apply_to(
+        self,
+        name: &mut String,
+        code_block: &mut CodeBlock,
+        symbol: &mut Symbol,
+        arg_info: &mut Vec<ArgInfo>,
+        description: &mut String,
+    ) {
+        if let Some(new_name) = self.name {
+            *name = new_name;
+        }
+        if let Some(new_code_block) = self.code_block {
+            *code_block = new_code_block;
+        }
+        if let Some(new_symbol) = self.symbol {
+            *symbol = new_symbol;
+        }
+        if let Some(new_arg_info) = self.arg_info {
+            *arg_info = new_arg_info;
+        }
+        if let Some(new_description) = self.description {
+            *description = new_description;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConfigOverlay;
+    use crate::error::BBScriptError;
+    use crate::game_config::ArgType;
+    use crate::SupportedGame;
+
+    #[test]
+    fn stack_overlays() {
+        let mut config = SupportedGame::Ggst.into_config();
+
+        let upstream = ConfigOverlay::new(
+            r#"(
+                named_variables: { 9000: "ComboCounter" },
+                named_value_maps: { "Stance": { 0: "Standing", 1: "Crouching" } },
+                instructions: {
+                    27: (name: "gotoState", description: "Switches to another state"),
+                    9000: (size: 8, name: "setStance", args: [Enum("Stance")]),
+                },
+            )"#
+            .as_bytes(),
+        )
+        .unwrap();
+        let private = ConfigOverlay::new(
+            r#"(
+                named_value_maps: { "Stance": { 1: "Crouch" } },
+                instructions: {
+                    27: (name: "changeState"),
+                },
+            )"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        config.apply_overlay(upstream).unwrap();
+        config.apply_overlay(private).unwrap();
+
+        let renamed = config.get_by_id(27).unwrap();
+        assert_eq!(renamed.name().as_deref(), Some("changeState"));
+        assert_eq!(renamed.size(), Some(36));
+        assert!(config.get_by_name("jumpToState").is_none());
+
+        let added = config.get_by_name("setStance").unwrap();
+        assert_eq!(added.id(), 9000);
+        assert_eq!(added.args(), [ArgType::Enum("Stance".into())]);
+        assert_eq!(
+            config.get_enum_value("Stance".into(), "Crouch".into()),
+            Some(1)
+        );
+        assert_eq!(
+            config.get_variable_by_name("ComboCounter".into()),
+            Some(9000)
+        );
+        // variables of the base config are kept
+        assert_eq!(config.get_variable_by_name("Tmp".into()), Some(0));
+    }
+
+    #[test]
+    fn invalid_overlays() {
+        let mut config = SupportedGame::Ggst.into_config();
+        let overlay = ConfigOverlay::new(
+            "(named_variables: { 9000: \"ComboCounter\" }, \
+             instructions: { 27: (name: \"changeState\"), 9000: (name: \"new\") })"
+                .as_bytes(),
+        );
+        assert!(matches!(
+            config.apply_overlay(overlay.unwrap()),
+            Err(BBScriptError::OverlayMissingSize(9000))
+        ));
+        // nothing from an invalid overlay is applied
+        assert!(config.get_variable_by_name("ComboCounter".into()).is_none());
+        assert!(config.get_by_name("changeState").is_none());
+        assert!(config.get_by_name("jumpToState").is_some());
+
+        let mut config = SupportedGame::Ggst.into_config();
+        let overlay = ConfigOverlay::new("(instructions: { 27: (name: \"sprite\") })".as_bytes());
+        assert!(matches!(
+            config.apply_overlay(overlay.unwrap()),
+            Err(BBScriptError::ConfigDuplicateName(names)) if names == ["sprite"]
+        ));
+        assert_eq!(
+            config.get_by_id(27).unwrap().name().as_deref(),
+            Some("jumpToState")
+        );
+    }
+}
@@ -4,7 +4,7 @@ use serde::Serialize;
 use smallvec::SmallVec;
 
 use std::fmt::Write;
-use std::io::Cursor;
+use std::io::{Cursor, Read};
 
 use crate::game_config::{
     ArgType, BBSNumber, CodeBlock, ScriptConfig, SizedInstruction, SizedString, TaggedValue,
@@ -15,6 +15,14 @@ use crate::HashMap;
 
 const INDENT_SPACES: usize = 2;
 
+/// Instructions paired with the offset they start at
+pub type OffsetProgram = Vec<(usize, InstructionValue)>;
+
+/// Size of the ID at the start of every instruction
+const ID_SIZE: usize = 0x4;
+/// Size of the ID and size at the start of every instruction in unsized configs
+const UNSIZED_HEADER_SIZE: usize = 0x8;
+
 #[derive(Debug, Clone, Serialize)]
 pub enum ArgValue {
     Unknown(SmallVec<[u8; 16]>),
@@ -33,12 +41,22 @@ pub struct InstructionValue {
     pub code_block: CodeBlock,
 }
 
+impl InstructionValue {
+    /// The instruction's name, or `Unknown` followed by its ID if it is unnamed
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("Unknown{}", self.id),
+        }
+    }
+}
+
 fn arg_to_string(config: &ScriptConfig, arg: &ArgValue) -> Result<String, BBScriptError> {
     match arg {
         ArgValue::Unknown(data) => Ok(format!("0x{}", hex::encode_upper(data))),
         ArgValue::Number(num) => Ok(format!("{num}")),
-        ArgValue::String16(s) => Ok(format!("s16'{s}'")),
-        ArgValue::String32(s) => Ok(format!("s32'{s}'")),
+        ArgValue::String16(s) => Ok(format!("s16'{}'", escaped(&s.0))),
+        ArgValue::String32(s) => Ok(format!("s32'{}'", escaped(&s.0))),
         ArgValue::AccessedValue(_tagged @ TaggedValue::Improper { tag, value }) => {
             Ok(format!("BadTag({tag}, {value})"))
         }
@@ -60,85 +78,238 @@ fn arg_to_string(config: &ScriptConfig, arg: &ArgValue) -> Result<String, BBScri
     }
 }
 
+/// Settings for writing scripts in the readable format.
+/// A plain `usize` converts into the default settings with that indent limit
+#[derive(Debug, Clone, Copy)]
+pub struct TextOptions {
+    /// Maximum number of nested blocks that increase the indentation
+    pub indent_limit: usize,
+    /// Writes args that are named in the config as `name=value`
+    pub keyword_args: bool,
+    /// Writes the description of each instruction and the meaning of its enum args as `//` comments
+    pub comments: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            indent_limit: 12,
+            keyword_args: false,
+            comments: false,
+        }
+    }
+}
+
+impl From<usize> for TextOptions {
+    fn from(indent_limit: usize) -> Self {
+        Self {
+            indent_limit,
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes the lines of a readable script, indenting the contents of blocks
+/// and leaving a blank line after each top level block
+pub(crate) struct ScriptWriter {
+    out: String,
+    indent: usize,
+    indent_limit: usize,
+}
+
+impl ScriptWriter {
+    pub(crate) fn new(indent_limit: usize) -> Self {
+        Self {
+            out: String::new(),
+            indent: 0,
+            indent_limit,
+        }
+    }
+
+    /// Writes a line, such as a comment, at the indentation of the next instruction
+    pub(crate) fn write_line(&mut self, line: &str) {
+        let indent_width = self.indent.clamp(0, self.indent_limit) * INDENT_SPACES;
+        self.out += &format!("{:indent_width$}{line}\n", "");
+    }
+
+    /// Writes the line of an instruction, then changes the indentation of the following lines
+    /// if the instruction begins or ends a block
+    pub(crate) fn write_instruction(&mut self, line: &str, code_block: CodeBlock) {
+        self.write_line(line);
+
+        match code_block {
+            CodeBlock::Begin => self.indent += 1,
+            CodeBlock::End if self.indent > 0 => {
+                self.indent -= 1;
+                if self.indent == 0 {
+                    self.out.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+}
+
 impl ScriptConfig {
     pub fn parse_to_string<B: ByteOrder>(
         &self,
         input: impl AsRef<[u8]>,
-        indent_limit: usize,
+        options: impl Into<TextOptions>,
     ) -> Result<String, BBScriptError> {
         let program = self.parse::<B>(input.as_ref())?;
-        let mut out = String::new();
 
-        let mut indent = 0;
-        let mut block_ended = false;
+        self.instructions_to_string(&program, options)
+    }
+
+    /// Writes already parsed instructions in the readable format produced by [`ScriptConfig::parse_to_string`]
+    pub fn instructions_to_string(
+        &self,
+        program: &[InstructionValue],
+        options: impl Into<TextOptions>,
+    ) -> Result<String, BBScriptError> {
+        let TextOptions {
+            indent_limit,
+            keyword_args,
+            comments,
+        } = options.into();
+        let mut writer = ScriptWriter::new(indent_limit);
+
         for instruction in program {
-            // indent the text
-            out.write_fmt(format_args!(
-                "{:indent$}",
-                "",
-                indent = (indent.clamp(0, indent_limit) * (INDENT_SPACES))
-            ))?;
-
-            let instruction_name = if let Some(name) = instruction.name {
-                name
+            if comments {
+                for comment in self.instruction_comments(instruction) {
+                    writer.write_line(&format!("// {comment}"));
+                }
+            }
+
+            let mut line = format!("{}: ", instruction.display_name());
+
+            let arg_info = if keyword_args {
+                self.arg_info(instruction.id)
             } else {
-                format!("Unknown{}", instruction.id)
+                &[]
             };
 
-            out.write_fmt(format_args!("{}: ", instruction_name))?;
-
-            let mut args = instruction.args.iter().peekable();
-            while let Some(arg) = args.next() {
-                out.write_fmt(format_args!("{}", arg_to_string(self, arg)?))?;
+            let mut args = instruction.args.iter().enumerate().peekable();
+            while let Some((index, arg)) = args.next() {
+                if let Some(info) = arg_info.get(index).filter(|info| !info.name.is_empty()) {
+                    line.write_fmt(format_args!("{}=", info.name))?;
+                }
+                line.write_fmt(format_args!("{}", arg_to_string(self, arg)?))?;
 
                 if args.peek().is_some() {
-                    out.write_fmt(format_args!(", "))?;
+                    line.write_fmt(format_args!(", "))?;
                 }
             }
 
-            out.write_char('\n')?;
-
-            match instruction.code_block {
-                CodeBlock::Begin => indent += 1,
-                CodeBlock::End => {
-                    if indent > 0 {
-                        indent -= 1;
-                        if indent == 0 {
-                            block_ended = true;
-                        }
-                    }
-                }
-                _ => {}
-            }
+            writer.write_instruction(&line, instruction.code_block);
+        }
+
+        Ok(writer.finish())
+    }
+
+    /// Lines describing an instruction and the meaning of its enum args, using the descriptions in the config
+    fn instruction_comments(&self, instruction: &InstructionValue) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .description(instruction.id)
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+
+        let arg_info = self.arg_info(instruction.id);
+        for (index, arg) in instruction.args.iter().enumerate() {
+            let ArgValue::Enum(enum_name, value) = arg else {
+                continue;
+            };
 
-            if block_ended {
-                out.write_char('\n')?;
-                block_ended = false;
+            let info = arg_info.get(index);
+            let mut line = match info {
+                Some(info) if !info.name.is_empty() => format!("{} is ", info.name),
+                _ => format!("arg {index} is "),
+            };
+            match self
+                .named_value_maps
+                .get(enum_name)
+                .and_then(|map| map.get_by_left(value))
+            {
+                Some(variant) => line += &format!("{variant} ({value}) of {enum_name}"),
+                None => line += &format!("{value} of {enum_name}"),
             }
+            if let Some(info) = info.filter(|info| !info.description.is_empty()) {
+                line += &format!(": {}", info.description.replace('\n', " "));
+            }
+
+            lines.push(line);
         }
 
-        Ok(out)
+        lines
     }
 
     pub fn parse<B: ByteOrder>(
         &self,
         input: impl AsRef<[u8]>,
     ) -> Result<Vec<InstructionValue>, BBScriptError> {
+        Ok(self
+            .parse_with_offsets::<B>(input)?
+            .into_iter()
+            .map(|(_, instruction)| instruction)
+            .collect())
+    }
+
+    /// Parses a script the same as [`ScriptConfig::parse`], but pairs each instruction
+    /// with the offset it starts at relative to the beginning of `input`
+    pub fn parse_with_offsets<B: ByteOrder>(
+        &self,
+        input: impl AsRef<[u8]>,
+    ) -> Result<Vec<(usize, InstructionValue)>, BBScriptError> {
+        self.parse_jump_tables_and_script::<B>(input.as_ref(), None)
+    }
+
+    /// Parses a script the same as [`ScriptConfig::parse_with_offsets`], but instead of failing
+    /// when a sized config is missing an instruction ID, skips ahead to the next plausible known instruction.
+    ///
+    /// The skipped bytes are kept as an instruction with the unknown ID and a single `Unknown` arg,
+    /// so the script still rebuilds to the original bytes. Unsized configs never need to skip bytes
+    pub fn parse_recovering<B: ByteOrder>(
+        &self,
+        input: impl AsRef<[u8]>,
+    ) -> Result<(OffsetProgram, Vec<SkippedBytes>), BBScriptError> {
+        let mut skipped = Vec::new();
+        let program = self.parse_jump_tables_and_script::<B>(input.as_ref(), Some(&mut skipped))?;
+
+        Ok((program, skipped))
+    }
+
+    fn parse_jump_tables_and_script<B: ByteOrder>(
+        &self,
+        input: &[u8],
+        skipped: Option<&mut Vec<SkippedBytes>>,
+    ) -> Result<Vec<(usize, InstructionValue)>, BBScriptError> {
         const JUMP_ENTRY_LENGTH: usize = 0x24;
 
-        let mut input = input.as_ref();
+        let mut input = Cursor::new(input);
+
+        // each jump table starts with a u32 count of its entries
+        let counts_size = 4 * self.jump_table_ids.len();
+        if counts_size > input.remaining() {
+            return Err(BBScriptError::TruncatedJumpTableCounts(
+                counts_size,
+                input.remaining(),
+            ));
+        }
 
         // get jump table size in bytes
-        let jump_table_size: usize = JUMP_ENTRY_LENGTH
-            * self
-                .jump_table_ids
-                .iter()
-                .map(|_| input.read_u32::<B>().unwrap() as usize)
-                .sum::<usize>();
+        let mut jump_table_size: usize = 0;
+        for _ in &self.jump_table_ids {
+            jump_table_size += JUMP_ENTRY_LENGTH * input.read_u32::<B>()? as usize;
+        }
 
         log::debug!("jump table size: {jump_table_size}");
 
-        if jump_table_size >= input.len() {
+        if jump_table_size >= input.remaining() {
             return Err(BBScriptError::IncorrectJumpTableSize(
                 jump_table_size.to_string(),
             ));
@@ -147,29 +318,46 @@ impl ScriptConfig {
         input.advance(jump_table_size);
 
         // parse the actual scripts
-        self.parse_script::<B>(input)
+        self.parse_script::<B>(input, skipped)
     }
 
+    /// Parses instructions from the current position of `input` to the end,
+    /// offsets are relative to the start of the underlying slice.
+    /// Unknown instructions are skipped and recorded in `skipped` if it is given
     fn parse_script<B: ByteOrder>(
         &self,
-        bytes: impl AsRef<[u8]>,
-    ) -> Result<Vec<InstructionValue>, BBScriptError> {
+        mut input: Cursor<&[u8]>,
+        mut skipped: Option<&mut Vec<SkippedBytes>>,
+    ) -> Result<Vec<(usize, InstructionValue)>, BBScriptError> {
         use crate::game_config::InstructionInfo;
 
-        let mut input = Cursor::new(bytes.as_ref());
-        let mut program = Vec::with_capacity(bytes.as_ref().len() / 2);
+        let mut program = Vec::with_capacity(input.remaining() / 2);
 
         match &self.instructions {
             InstructionInfo::Sized(id_map) => {
                 while input.remaining() != 0 {
-                    program.push(self.parse_sized::<B>(id_map, &mut input)?);
+                    let offset = input.position() as usize;
+
+                    let instruction =
+                        match (self.parse_sized::<B>(id_map, &mut input), &mut skipped) {
+                            (Err(BBScriptError::UnknownInstructionID(id)), Some(skipped)) => {
+                                let (instruction, skip) =
+                                    skip_unknown::<B>(id_map, &mut input, offset, id);
+                                skipped.push(skip);
+                                instruction
+                            }
+                            (result, _) => result?,
+                        };
+
+                    program.push((offset, instruction));
                 }
 
                 Ok(program)
             }
             InstructionInfo::Unsized(id_map) => {
                 while input.remaining() != 0 {
-                    program.push(self.parse_unsized::<B>(id_map, &mut input)?);
+                    let offset = input.position() as usize;
+                    program.push((offset, self.parse_unsized::<B>(id_map, &mut input)?));
                 }
 
                 Ok(program)
@@ -182,6 +370,9 @@ impl ScriptConfig {
         id_map: &HashMap<u32, SizedInstruction>,
         input: &mut Cursor<&[u8]>,
     ) -> Result<InstructionValue, BBScriptError> {
+        let start = input.position() as usize;
+
+        check_remaining(input, start, None, ID_SIZE)?;
         let instruction_id = input.read_u32::<B>()?;
 
         let instruction = if let Some(instruction) = id_map.get(&instruction_id) {
@@ -190,17 +381,21 @@ impl ScriptConfig {
             return Err(BBScriptError::UnknownInstructionID(instruction_id));
         };
 
+        // args in the config may be larger than the size of the instruction
+        let arg_types = instruction.args();
+        let needed = ID_SIZE + arg_types.iter().map(ArgType::size).sum::<usize>();
+        check_remaining(input, start, Some(instruction_id), needed)?;
+
         let instruction_name = if instruction.name.is_empty() {
             None
         } else {
             Some(instruction.name.clone())
         };
 
-        let args = instruction
-            .args()
+        let args = arg_types
             .into_iter()
             .map(|arg_type| self.parse_argument::<B>(arg_type, input))
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         let instruction = InstructionValue {
             id: instruction_id,
@@ -220,8 +415,14 @@ impl ScriptConfig {
     ) -> Result<InstructionValue, BBScriptError> {
         log::debug!("offset {:#X} from end of file", input.remaining());
 
-        let instruction_id = input.read_u32::<B>().unwrap();
-        let instruction_size = input.read_u32::<B>().unwrap();
+        let start = input.position() as usize;
+
+        check_remaining(input, start, None, ID_SIZE)?;
+        let instruction_id = input.read_u32::<B>()?;
+
+        check_remaining(input, start, Some(instruction_id), UNSIZED_HEADER_SIZE)?;
+        let instruction_size = input.read_u32::<B>()? as usize;
+        check_remaining(input, start, Some(instruction_id), instruction_size)?;
 
         log::info!(
             "finding info for instruction with ID {instruction_id} and size {instruction_size}"
@@ -240,11 +441,24 @@ impl ScriptConfig {
             Some(instruction.name.clone())
         };
 
-        let args = instruction
-            .args_with_known_size(instruction_size as usize)
+        // the args in the config must fit in the size given by the instruction
+        let arg_types = instruction
+            .args_with_known_size(instruction_size)
+            .ok_or_else(|| {
+                let needed =
+                    UNSIZED_HEADER_SIZE + instruction.args.iter().map(ArgType::size).sum::<usize>();
+                BBScriptError::TruncatedInstruction(
+                    start,
+                    Some(instruction_id),
+                    needed,
+                    instruction_size,
+                )
+            })?;
+
+        let args = arg_types
             .into_iter()
             .map(|arg_type| self.parse_argument::<B>(arg_type, input))
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         let instruction = InstructionValue {
             id: instruction_id,
@@ -261,45 +475,177 @@ impl ScriptConfig {
         &self,
         arg_type: ArgType,
         input: &mut Cursor<&[u8]>,
-    ) -> ArgValue {
-        match arg_type {
+    ) -> Result<ArgValue, BBScriptError> {
+        Ok(match arg_type {
             // get SmallVec of bytes
             ArgType::Unknown(n) => {
-                ArgValue::Unknown((0..n).map(|_| input.read_u8().unwrap()).collect())
+                let mut buf = SmallVec::from_elem(0, n);
+                input.read_exact(&mut buf)?;
+
+                ArgValue::Unknown(buf)
             }
             ArgType::String16 => {
                 let mut buf = [0; ArgType::STRING16_SIZE];
-                input.copy_to_slice(&mut buf);
+                input.read_exact(&mut buf)?;
 
                 ArgValue::String16(SizedString(process_string_buf(&buf)))
             }
             ArgType::String32 => {
                 let mut buf = [0; ArgType::STRING32_SIZE];
-                input.copy_to_slice(&mut buf);
+                input.read_exact(&mut buf)?;
 
                 ArgValue::String32(SizedString(process_string_buf(&buf)))
             }
-            ArgType::Number => ArgValue::Number(input.read_i32::<B>().unwrap()),
-            ArgType::Enum(s) => ArgValue::Enum(s.clone(), input.read_i32::<B>().unwrap()),
+            ArgType::Number => ArgValue::Number(input.read_i32::<B>()?),
+            ArgType::Enum(s) => ArgValue::Enum(s.clone(), input.read_i32::<B>()?),
             ArgType::AccessedValue => {
-                let tag = input.read_i32::<B>().unwrap();
+                let tag = input.read_i32::<B>()?;
 
                 if tag == self.literal_tag {
-                    ArgValue::AccessedValue(TaggedValue::Literal(input.read_i32::<B>().unwrap()))
+                    ArgValue::AccessedValue(TaggedValue::Literal(input.read_i32::<B>()?))
                 } else if tag == self.variable_tag {
-                    ArgValue::AccessedValue(TaggedValue::Variable(input.read_i32::<B>().unwrap()))
+                    ArgValue::AccessedValue(TaggedValue::Variable(input.read_i32::<B>()?))
                 } else {
                     log::warn!(
                         "found improperly tagged AccessedValue, most likely just two Numbers"
                     );
                     ArgValue::AccessedValue(TaggedValue::Improper {
                         tag,
-                        value: input.read_i32::<B>().unwrap(),
+                        value: input.read_i32::<B>()?,
                     })
                 }
             }
+        })
+    }
+}
+
+/// Number of consecutive known instructions that must follow a position for it to be
+/// considered the start of an instruction when recovering from an unknown ID
+const RESYNC_CHAIN_LENGTH: usize = 3;
+
+/// Bytes skipped by [`ScriptConfig::parse_recovering`], starting with an instruction ID missing from the config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedBytes {
+    /// Offset of the unknown instruction relative to the start of the input
+    pub offset: usize,
+    pub instruction_id: u32,
+    /// Number of bytes skipped, including the instruction ID
+    pub len: usize,
+}
+
+/// Skips from an unknown instruction at `start` to the next plausible known instruction, or to the end of `input`.
+/// Instructions are assumed to be 4 byte aligned relative to each other.
+///
+/// The earliest plausible position is used, so the end of the unknown instruction can be mistaken for a short
+/// known instruction if its last args happen to look like one
+fn skip_unknown<B: ByteOrder>(
+    id_map: &HashMap<u32, SizedInstruction>,
+    input: &mut Cursor<&[u8]>,
+    start: usize,
+    instruction_id: u32,
+) -> (InstructionValue, SkippedBytes) {
+    let bytes = *input.get_ref();
+
+    let resume = (start + ID_SIZE..bytes.len())
+        .step_by(ID_SIZE)
+        .find(|&position| is_plausible_start::<B>(id_map, bytes, position))
+        .unwrap_or(bytes.len());
+
+    log::warn!("skipped unknown instruction {instruction_id} from {start:#X} to {resume:#X}");
+    input.set_position(resume as u64);
+
+    let instruction = InstructionValue {
+        id: instruction_id,
+        name: None,
+        args: smallvec::smallvec![ArgValue::Unknown(SmallVec::from_slice(
+            &bytes[start + ID_SIZE..resume],
+        ))],
+        code_block: CodeBlock::NoBlock,
+    };
+
+    let skipped = SkippedBytes {
+        offset: start,
+        instruction_id,
+        len: resume - start,
+    };
+
+    (instruction, skipped)
+}
+
+/// Checks if a chain of known instructions starts at `position`, ending either at the end of the
+/// input or after [`RESYNC_CHAIN_LENGTH`] instructions
+fn is_plausible_start<B: ByteOrder>(
+    id_map: &HashMap<u32, SizedInstruction>,
+    bytes: &[u8],
+    mut position: usize,
+) -> bool {
+    for _ in 0..RESYNC_CHAIN_LENGTH {
+        if position == bytes.len() {
+            return true;
+        }
+
+        let Some(id_bytes) = bytes.get(position..position + ID_SIZE) else {
+            return false;
+        };
+
+        match id_map.get(&B::read_u32(id_bytes)) {
+            Some(instruction)
+                if instruction.size >= ID_SIZE
+                    && has_plausible_strings(instruction, &bytes[position..]) =>
+            {
+                position += instruction.size
+            }
+            _ => return false,
         }
     }
+
+    position <= bytes.len()
+}
+
+/// Checks that every string arg of an instruction starting at the beginning of `bytes` is a
+/// non-empty run of printable ASCII padded with zeros. Empty strings are rejected as runs of zeros
+/// are common in script data, which only delays recovery by an instruction if they were legitimate
+pub(crate) fn has_plausible_strings(instruction: &SizedInstruction, bytes: &[u8]) -> bool {
+    let mut position = ID_SIZE;
+
+    instruction.args().iter().all(|arg| {
+        let start = position;
+        position += arg.size();
+
+        if !matches!(arg, ArgType::String16 | ArgType::String32) {
+            return true;
+        }
+
+        let Some(string) = bytes.get(start..position) else {
+            return false;
+        };
+        let text_len = string.iter().position(|b| *b == 0).unwrap_or(string.len());
+
+        text_len > 0
+            && string[..text_len].iter().all(|b| (0x20..0x7F).contains(b))
+            && string[text_len..].iter().all(|b| *b == 0)
+    })
+}
+
+/// Returns an error if fewer than `needed` bytes remain in `input` for the instruction starting at `start`
+fn check_remaining(
+    input: &Cursor<&[u8]>,
+    start: usize,
+    instruction_id: Option<u32>,
+    needed: usize,
+) -> Result<(), BBScriptError> {
+    let available = input.get_ref().len() - start;
+
+    if available < needed {
+        return Err(BBScriptError::TruncatedInstruction(
+            start,
+            instruction_id,
+            needed,
+            available,
+        ));
+    }
+
+    Ok(())
 }
 
 fn process_string_buf(buf: &[u8]) -> String {
@@ -307,5 +653,131 @@ fn process_string_buf(buf: &[u8]) -> String {
         .filter(|x| **x != 0)
         .map(|x| *x as char)
         .collect::<String>()
-        .replace('\'', r"\'")
+}
+
+pub(crate) fn escaped(string: &str) -> String {
+    string.replace('\'', r"\'")
+}
+
+#[cfg(test)]
+mod test {
+    use super::SkippedBytes;
+    use crate::game_config::test::config;
+    use crate::game_config::ScriptConfig;
+    use crate::rebuilder::{rebuild_bbscript, rebuild_from_instructions};
+    use crate::BBScriptError;
+    use byteorder::{ByteOrder, LittleEndian};
+
+    #[test]
+    fn truncated_scripts() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+        let bytes = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'\n  spriteTimeVariable: s32'spr', Mem(Tmp)\nendState:".into(),
+        )
+        .unwrap();
+
+        // the sprite instruction starts after a single jump table entry and the 40 byte `beginState`
+        let sprite_start = 0x8 + 0x24 + 0x28;
+        let sprite_id = LittleEndian::read_u32(&bytes[sprite_start..]);
+
+        let truncated = &bytes[..sprite_start + 0x10];
+        assert!(matches!(
+            config.parse::<LittleEndian>(truncated),
+            Err(BBScriptError::TruncatedInstruction(offset, Some(id), _, 0x10))
+                if offset == sprite_start && id == sprite_id
+        ));
+
+        assert!(matches!(
+            config.parse::<LittleEndian>(&bytes[..0x6]),
+            Err(BBScriptError::TruncatedJumpTableCounts(0x8, 0x6))
+        ));
+
+        let truncated = &bytes[..sprite_start + 0x2];
+        assert!(matches!(
+            config.parse::<LittleEndian>(truncated),
+            Err(BBScriptError::TruncatedInstruction(_, None, 0x4, 0x2))
+        ));
+
+        // an instruction whose size on disk is smaller than its args in the config
+        let mut shrunk = bytes.clone();
+        LittleEndian::write_u32(&mut shrunk[sprite_start + 0x4..], 0x8);
+        assert!(matches!(
+            config.parse::<LittleEndian>(shrunk),
+            Err(BBScriptError::TruncatedInstruction(_, Some(_), _, 0x8))
+        ));
+    }
+
+    #[test]
+    fn recover_from_unknown_ids() {
+        let config = ScriptConfig::load("./static_db/ggst.ron").unwrap();
+        let mut bytes = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'
+  sprite: s32'a', 100000
+  sprite: s32'b', 4
+  sprite: s32'c', 5
+endState:"
+                .into(),
+        )
+        .unwrap();
+
+        // one jump table entry followed by the 36 byte `beginState`.
+        // the duration of the sprite being skipped isn't a valid ID, so it can't be mistaken for an instruction
+        let sprite_start = 0x4 + 0x24 + 0x24;
+        LittleEndian::write_u32(&mut bytes[sprite_start..], 0xFFFF);
+
+        assert!(matches!(
+            config.parse::<LittleEndian>(&bytes),
+            Err(BBScriptError::UnknownInstructionID(0xFFFF))
+        ));
+
+        let (program, skipped) = config.parse_recovering::<LittleEndian>(&bytes).unwrap();
+        assert_eq!(
+            skipped,
+            [SkippedBytes {
+                offset: sprite_start,
+                instruction_id: 0xFFFF,
+                len: 0x28,
+            }]
+        );
+        assert_eq!(program.len(), 5);
+
+        let program: Vec<_> = program.into_iter().map(|(_, i)| i).collect();
+        assert_eq!(
+            rebuild_from_instructions::<LittleEndian>(&config, &program).unwrap(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn description_comments() {
+        let config = config();
+        let options = crate::TextOptions {
+            comments: true,
+            ..Default::default()
+        };
+
+        let script = "beginState: s32'A'\n  face: (Left), 5\nendState:";
+        let bytes = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap();
+        let text = config
+            .parse_to_string::<LittleEndian>(&bytes, options)
+            .unwrap();
+
+        assert_eq!(
+            text,
+            "beginState: s32'A'\n  \
+            // Faces a side\n  \
+            // after turning\n  \
+            // from is Left (0) of Direction: Where the character faced\n  \
+            // arg 1 is 5 of Direction\n  \
+            face: (Left), 5\n  \
+            endState: \n\n"
+        );
+        // comments are skipped when rebuilding
+        assert_eq!(
+            rebuild_bbscript::<LittleEndian>(&config, text).unwrap(),
+            bytes
+        );
+    }
 }
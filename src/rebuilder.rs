@@ -1,33 +1,429 @@
+use std::collections::HashSet;
 use std::io::Write;
+use std::ops::Range;
 
 use crate::{
     error::BBScriptError,
-    game_config::{ArgType, GenericInstruction, ScriptConfig, SizedString, UnsizedInstruction},
+    game_config::{
+        ArgType, CodeBlock, GenericInstruction, ScriptConfig, SizedString, Symbol, SymbolKind,
+        TaggedValue, UnsizedInstruction,
+    },
+    parser::{escaped, ArgValue, InstructionValue},
 };
 
 use byteorder::{ByteOrder, WriteBytesExt};
 use pest_consume::{match_nodes, Parser};
 
+/// Names of states and subroutines defined outside of the script being rebuilt, such as in a common script
+#[derive(Debug, Clone, Default)]
+pub struct ExternalSymbols {
+    names: HashSet<(SymbolKind, String)>,
+}
+
+impl ExternalSymbols {
+    pub fn insert(&mut self, kind: SymbolKind, name: impl Into<String>) {
+        self.names.insert((kind, name.into()));
+    }
+
+    pub fn contains(&self, kind: SymbolKind, name: &str) -> bool {
+        self.names.contains(&(kind, name.to_string()))
+    }
+
+    /// Adds every state and subroutine defined in a readable script
+    pub fn add_script(&mut self, db: &ScriptConfig, script: &str) -> Result<(), BBScriptError> {
+        let mut program = parse_program(script)?;
+
+        let errors = resolve_arg_names(&mut program, db, script);
+        if !errors.is_empty() {
+            return Err(BBScriptError::from_errors(errors));
+        }
+
+        for instruction in program {
+            if let Symbol::Defines(kind @ (SymbolKind::State | SymbolKind::Subroutine)) =
+                find_instruction_info(db, &instruction, script)?.symbol()
+            {
+                for name in instruction.symbol_names() {
+                    self.insert(kind, name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub fn rebuild_bbscript<B: ByteOrder>(
-    db: ScriptConfig,
+    db: &ScriptConfig,
+    script: String,
+) -> Result<Vec<u8>, BBScriptError> {
+    rebuild_bbscript_with_symbols::<B>(db, script, &ExternalSymbols::default())
+}
+
+/// Rebuilds a readable script, allowing references to states and subroutines in `external`
+/// in addition to the ones defined in the script itself
+pub fn rebuild_bbscript_with_symbols<B: ByteOrder>(
+    db: &ScriptConfig,
     script: String,
+    external: &ExternalSymbols,
 ) -> Result<Vec<u8>, BBScriptError> {
-    let root = BBSParser::parse(Rule::program, &script)
+    let mut program = parse_program(&script)?;
+
+    // every error found is reported at once, rather than stopping at the first one
+    let mut errors = resolve_arg_names(&mut program, db, &script);
+    errors.extend(check_symbols(&program, db, external, &script));
+
+    match assemble_script::<B>(program, db, &script) {
+        Ok(file) if errors.is_empty() => Ok(file),
+        result => {
+            errors.extend(result.err().unwrap_or_default());
+            Err(BBScriptError::from_errors(errors))
+        }
+    }
+}
+
+/// Rebuilds a readable script without checking that referenced names are defined
+pub(crate) fn rebuild_bbscript_unchecked<B: ByteOrder>(
+    db: &ScriptConfig,
+    script: String,
+) -> Result<Vec<u8>, BBScriptError> {
+    let mut program = parse_program(&script)?;
+
+    let errors = resolve_arg_names(&mut program, db, &script);
+    if !errors.is_empty() {
+        return Err(BBScriptError::from_errors(errors));
+    }
+
+    assemble_script::<B>(program, db, &script).map_err(BBScriptError::from_errors)
+}
+
+pub(crate) fn parse_program(script: &str) -> Result<Vec<BBSFunction>, BBScriptError> {
+    let root = BBSParser::parse(Rule::program, script)
         .and_then(|p| p.single())
         .map_err(Box::new)?;
 
     log::trace!("Parsed program AST:\n{:#?}", &root);
     let program = BBSParser::program(root).map_err(Box::new)?;
 
-    let file = assemble_script::<B>(program, &db)?;
+    Ok(program)
+}
+
+/// Finds the `//` and `/* */` comments of a readable script, which the grammar skips.
+/// A block comment that is never closed runs to the end of the script
+pub(crate) fn comment_ranges(script: &str) -> Vec<Range<usize>> {
+    let mut comments = Vec::new();
+    let mut in_string = false;
+
+    let mut chars = script.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match (c, chars.peek().map(|(_, next)| *next)) {
+            // only quotes can be escaped within strings
+            ('\\', Some('\'')) if in_string => {
+                chars.next();
+            }
+            ('\'', _) => in_string = !in_string,
+            ('/', Some(next @ ('/' | '*'))) if !in_string => {
+                let end = if next == '/' {
+                    script[start..]
+                        .find(['\n', '\r'])
+                        .map_or(script.len(), |i| start + i)
+                } else {
+                    script[start + 2..]
+                        .find("*/")
+                        .map_or(script.len(), |i| start + 2 + i + 2)
+                };
+                comments.push(start..end);
+
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+            }
+            _ => {}
+        }
+    }
+
+    comments
+}
+
+/// Returns `true` if `name` is read back unchanged as the name of an instruction
+pub(crate) fn is_readable_instruction_name(name: &str) -> bool {
+    parse_program(&format!("{name}:"))
+        .is_ok_and(|program| matches!(program.as_slice(), [function] if function.name == name))
+}
+
+/// Returns `true` if `name` is read back unchanged as the variant of an enum
+pub(crate) fn is_readable_variant_name(name: &str) -> bool {
+    parse_program(&format!("f: ({name})")).is_ok_and(
+        |program| matches!(program[0].args.as_slice(), [ParserValue::Named(arg)] if arg == name),
+    )
+}
+
+/// Returns `true` if `name` is read back unchanged as the name of an argument
+pub(crate) fn is_readable_arg_name(name: &str) -> bool {
+    parse_program(&format!("f: {name}=0"))
+        .is_ok_and(|program| matches!(program[0].arg_names.as_slice(), [Some(arg)] if arg == name))
+}
+
+/// Returns `true` if `name` is read back unchanged as the name of a variable
+pub(crate) fn is_readable_variable_name(name: &str) -> bool {
+    parse_program(&format!("f: Mem({name})")).is_ok_and(
+        |program| matches!(program[0].args.as_slice(), [ParserValue::NamedMem(arg)] if arg == name),
+    )
+}
+
+/// Rebuilds a script from already parsed instructions, such as ones returned by [`ScriptConfig::parse`].
+/// Referenced names are not checked, see [`check_instruction_symbols`]
+pub fn rebuild_from_instructions<B: ByteOrder>(
+    db: &ScriptConfig,
+    program: &[InstructionValue],
+) -> Result<Vec<u8>, BBScriptError> {
+    let program = instructions_to_functions(program);
+
+    assemble_script::<B>(program, db, "").map_err(BBScriptError::from_errors)
+}
+
+/// Checks that every name referenced by an instruction is defined, either in `program` or in `external`.
+/// Errors report the index of the instruction in `program`
+pub fn check_instruction_symbols(
+    db: &ScriptConfig,
+    program: &[InstructionValue],
+    external: &ExternalSymbols,
+) -> Result<(), BBScriptError> {
+    let errors = check_symbols(&instructions_to_functions(program), db, external, "");
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(BBScriptError::from_errors(errors))
+    }
+}
+
+fn instructions_to_functions(program: &[InstructionValue]) -> Vec<BBSFunction> {
+    program
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| BBSFunction {
+            index: Some(index),
+            ..BBSFunction::from(instruction)
+        })
+        .collect()
+}
+
+/// Finds the config entry for an instruction by name, or by ID for `Unknown` instructions
+pub(crate) fn find_instruction_info(
+    db: &ScriptConfig,
+    instruction: &BBSFunction,
+    source: &str,
+) -> Result<GenericInstruction, BBScriptError> {
+    if let Some(i) = db.get_by_name(&instruction.name) {
+        return Ok(i);
+    }
+
+    log::trace!("could not locate instruction by name, trying by ID");
+    if let Ok(id) = instruction.name.trim_start_matches("Unknown").parse() {
+        if let Some(i) = db.get_by_id(id) {
+            Ok(i)
+        } else {
+            log::warn!(
+                "could not locate instruction {id} in config, using dynamic instruction size!"
+            );
+            let args = instruction
+                .args
+                .iter()
+                .enumerate()
+                .map(|(index, arg)| {
+                    arg.to_arg_type()
+                        .map_err(|e| e.at(source, instruction.arg_span(index)))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(GenericInstruction::Unsized(
+                id,
+                UnsizedInstruction::from_parsed(args),
+            ))
+        }
+    } else {
+        Err(
+            BBScriptError::UnknownInstructionName(instruction.name.clone())
+                .at(source, instruction.name_span()),
+        )
+    }
+}
+
+/// Moves arguments given as `name=value` to the position the config gives the argument with that name,
+/// so that they are written in the order the game reads them.
+/// Returns an error for each argument name that is unknown or given more than once, and each argument left out.
+///
+/// Instructions without named arguments or missing from the config are left as they are
+pub(crate) fn resolve_arg_names(
+    program: &mut [BBSFunction],
+    db: &ScriptConfig,
+    source: &str,
+) -> Vec<BBScriptError> {
+    let mut errors = Vec::new();
+
+    for instruction in program {
+        if !instruction.has_unresolved_arg_names() {
+            continue;
+        }
+        let Ok(info) = find_instruction_info(db, instruction, source) else {
+            continue;
+        };
+
+        match arg_order(instruction, &info) {
+            Ok(order) => instruction.reorder_args(&order),
+            Err(arg_errors) => errors.extend(
+                arg_errors
+                    .into_iter()
+                    .map(|(error, span)| error.at(source, span)),
+            ),
+        }
+    }
+
+    errors
+}
+
+/// Finds the index of the given argument to write at each position of the instruction's arguments
+fn arg_order(
+    instruction: &BBSFunction,
+    info: &GenericInstruction,
+) -> Result<Vec<usize>, Vec<ArgOrderError>> {
+    let mut errors = Vec::new();
+
+    // sized instructions read any bytes left over after their known args as one more arg
+    let mut positions: Vec<Option<usize>> = vec![None; info.all_args().len()];
+    let mut named_arg_seen = false;
+
+    for (index, arg_name) in instruction.arg_names.iter().enumerate() {
+        let span = instruction.arg_span(index);
+
+        let position = match arg_name {
+            None if named_arg_seen => {
+                let error = BBScriptError::PositionalArgAfterNamed(instruction.name.clone());
+                errors.push((error, span));
+
+                // only the misplaced arg is reported, rather than also reporting its position as missing
+                if let Some(position @ None) = positions.get_mut(index) {
+                    *position = Some(index);
+                }
+                continue;
+            }
+            None => index,
+            Some(arg_name) => {
+                named_arg_seen = true;
+
+                match info.arg_info().iter().position(|arg| &arg.name == arg_name) {
+                    Some(position) => position,
+                    None => {
+                        let error = BBScriptError::UnknownArgName(
+                            arg_name.clone(),
+                            instruction.name.clone(),
+                        );
+                        errors.push((error, span));
+                        continue;
+                    }
+                }
+            }
+        };
+
+        if position >= positions.len() {
+            positions.resize(position + 1, None);
+        }
+
+        if positions[position].replace(index).is_some() {
+            let error =
+                BBScriptError::DuplicateArg(describe_arg(info, position), instruction.name.clone());
+            errors.push((error, span));
+        }
+    }
+
+    for (position, _) in positions.iter().enumerate().filter(|(_, i)| i.is_none()) {
+        let error =
+            BBScriptError::MissingArg(describe_arg(info, position), instruction.name.clone());
+        errors.push((error, instruction.span.clone()));
+    }
+
+    if errors.is_empty() {
+        Ok(positions.into_iter().flatten().collect())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Describes an argument by its name if it has one, or its index otherwise
+pub(crate) fn describe_arg(info: &GenericInstruction, position: usize) -> String {
+    match info.arg_info().get(position) {
+        Some(arg) if !arg.name.is_empty() => format!("`{}`", arg.name),
+        _ => position.to_string(),
+    }
+}
+
+/// Checks that every name referenced by an instruction is defined, returning an error for each one that isn't.
+/// States and subroutines can be referenced from anywhere, but labels can only be referenced
+/// from within the top level block that defines them.
+///
+/// Instructions missing from the config are skipped, they are reported by [`assemble_script`]
+fn check_symbols(
+    program: &[BBSFunction],
+    db: &ScriptConfig,
+    external: &ExternalSymbols,
+    source: &str,
+) -> Vec<BBScriptError> {
+    // symbols are keyed by the index of the top level block they're defined in if they're local to it
+    let mut defined = HashSet::new();
+    let mut references = Vec::new();
+
+    let mut depth: usize = 0;
+    let mut top_level_block = 0;
+
+    for instruction in program {
+        let Ok(info) = find_instruction_info(db, instruction, source) else {
+            continue;
+        };
+
+        if info.block_type() == CodeBlock::Begin {
+            if depth == 0 {
+                top_level_block += 1;
+            }
+            depth += 1;
+        }
+
+        let scope = |kind| (kind == SymbolKind::Label).then_some(top_level_block);
 
-    Ok(file)
+        for name in instruction.symbol_names() {
+            match info.symbol() {
+                Symbol::Defines(kind) => {
+                    defined.insert((scope(kind), kind, name));
+                }
+                Symbol::References(kind) => {
+                    references.push((scope(kind), kind, name, instruction));
+                }
+                Symbol::NoSymbol => {}
+            }
+        }
+
+        if info.block_type() == CodeBlock::End {
+            depth = depth.saturating_sub(1);
+        }
+    }
+
+    references
+        .into_iter()
+        .filter(|(scope, kind, name, _)| {
+            !defined.contains(&(*scope, *kind, *name)) && !external.contains(*kind, name)
+        })
+        .map(|(_, kind, name, instruction)| {
+            BBScriptError::UndefinedSymbol(kind, name.to_string(), instruction.index)
+                .at(source, instruction.span.clone())
+        })
+        .collect()
 }
 
+/// Assembles parsed functions into a script, `source` is the readable script used to locate errors.
+/// Assembly continues past bad instructions so that every error in the script is returned
 fn assemble_script<B: ByteOrder>(
     program: Vec<BBSFunction>,
     db: &ScriptConfig,
-) -> Result<Vec<u8>, BBScriptError> {
+    source: &str,
+) -> Result<Vec<u8>, Vec<BBScriptError>> {
+    let mut errors = Vec::new();
+
     // current position of the reader
     let mut offset: u32 = 0x0;
     let mut script_buffer: Vec<u8> = Vec::new();
@@ -35,27 +431,21 @@ fn assemble_script<B: ByteOrder>(
     // TODO: figure out behavior around eliminating duplicate state jump entries
     // let mut previous_jump_entries = std::collections::HashSet::new();
 
-    let mut jump_entry_count = 0;
-    let mut jump_table_buffer: Vec<u8> = Vec::new();
+    // one entry count and table buffer per configured jump table ID, kept in config order
+    let mut jump_tables: Vec<(u32, Vec<u8>)> = vec![(0, Vec::new()); db.jump_table_ids.len()];
 
     for instruction in program {
+        // errors in named arguments were already reported by `resolve_arg_names`
+        if instruction.has_unresolved_arg_names() {
+            continue;
+        }
+
         log::debug!("finding info for {}", instruction.name);
-        let instruction_info = if let Some(i) = db.get_by_name(&instruction.name) {
-            i
-        } else {
-            log::trace!("could not locate instruction by name, trying by ID");
-            if let Ok(id) = instruction.name.trim_start_matches("Unknown").parse() {
-                if let Some(i) = db.get_by_id(id) {
-                    i
-                } else {
-                    log::warn!("could not locate instruction {id} in config, using dynamic instruction size!");
-                    let args = instruction.args.iter().map(|x| x.to_arg_type()).collect();
-                    GenericInstruction::Unsized(id, UnsizedInstruction::from_parsed(args))
-                }
-            } else {
-                return Err(BBScriptError::UnknownInstructionName(
-                    instruction.name.clone(),
-                ));
+        let instruction_info = match find_instruction_info(db, &instruction, source) {
+            Ok(info) => info,
+            Err(e) => {
+                errors.push(e);
+                continue;
             }
         };
 
@@ -64,11 +454,12 @@ fn assemble_script<B: ByteOrder>(
         // if the instruction is sized, check that its size matches the config entry
         if let Some(instruction_size) = instruction_info.size() {
             if instruction.total_size() != instruction_size {
-                return Err(BBScriptError::IncorrectFunctionSize(
+                let error = BBScriptError::IncorrectFunctionSize(
                     instruction.name.to_string(),
                     instruction.total_size(),
                     instruction_size,
-                ));
+                );
+                errors.push(error.at(source, instruction.span.clone()));
             }
         }
 
@@ -83,14 +474,15 @@ fn assemble_script<B: ByteOrder>(
         }
 
         // build state jump table
-        if db.is_jump_entry_id(instruction_info.id()) {
-            if let Some(ParserValue::String32(name)) = instruction.args.get(0) {
+        if let Some(table_index) = db.jump_table_index(instruction_info.id()) {
+            if let Some(ParserValue::String32(name)) = instruction.args.first() {
                 // this check deduplicates jump table entries
                 // if previous_jump_entries.insert(name.clone())
 
-                jump_table_buffer.write_all(&name.to_vec()).unwrap();
-                jump_table_buffer.write_u32::<B>(offset).unwrap();
-                jump_entry_count += 1;
+                let (entry_count, table_buffer) = &mut jump_tables[table_index];
+                table_buffer.write_all(&name.to_vec()).unwrap();
+                table_buffer.write_u32::<B>(offset).unwrap();
+                *entry_count += 1;
             }
         }
 
@@ -102,6 +494,8 @@ fn assemble_script<B: ByteOrder>(
                 &instruction.name
             );
 
+            let arg_error = |error: BBScriptError| error.at(source, instruction.arg_span(index));
+
             match arg {
                 ParserValue::String32(string) => {
                     script_buffer.append(&mut string.to_vec())
@@ -112,33 +506,38 @@ fn assemble_script<B: ByteOrder>(
                 ParserValue::Raw(data) => script_buffer.append(&mut data.to_vec()),
                 &ParserValue::Number(num) => script_buffer.write_i32::<B>(num).unwrap(),
                 ParserValue::Named(variant) => {
-                    let enum_name =
-                        if let Some(ArgType::Enum(name)) = instruction_info.args().get(index) {
-                            name.to_string()
-                        } else {
-                            return Err(BBScriptError::NoEnum(index, instruction_info.id()));
-                        };
-
-                    if let Some(value) = db.get_enum_value(enum_name.clone(), variant.to_string()) {
-                        script_buffer.write_i32::<B>(value).unwrap();
-                    } else {
-                        return Err(BBScriptError::NoAssociatedValue(
-                            variant.to_string(),
-                            enum_name,
-                        ));
-                    }
+                    let value = match instruction_info.args().get(index) {
+                        Some(ArgType::Enum(enum_name)) => db
+                            .get_enum_value(enum_name.to_string(), variant.to_string())
+                            .ok_or_else(|| {
+                                BBScriptError::NoAssociatedValue(
+                                    variant.to_string(),
+                                    enum_name.to_string(),
+                                )
+                            }),
+                        _ => Err(BBScriptError::NoEnum(index, instruction_info.id())),
+                    };
+
+                    // a placeholder keeps the rest of the script assembling after an error
+                    let value = value.unwrap_or_else(|e| {
+                        errors.push(arg_error(e));
+                        0
+                    });
+                    script_buffer.write_i32::<B>(value).unwrap();
                 }
                 &ParserValue::Mem(var_id) => {
                     script_buffer.write_i32::<B>(db.variable_tag).unwrap();
                     script_buffer.write_i32::<B>(var_id).unwrap();
                 }
                 ParserValue::NamedMem(var_name) => {
-                    let var_id = if let Some(var_id) = db.get_variable_by_name(var_name.to_string())
-                    {
-                        var_id
-                    } else {
-                        return Err(BBScriptError::NoVariableName(var_name.to_string()));
-                    };
+                    let var_id = db
+                        .get_variable_by_name(var_name.to_string())
+                        .unwrap_or_else(|| {
+                            errors.push(arg_error(BBScriptError::NoVariableName(
+                                var_name.to_string(),
+                            )));
+                            0
+                        });
 
                     script_buffer.write_i32::<B>(db.variable_tag).unwrap();
                     script_buffer.write_i32::<B>(var_id).unwrap();
@@ -161,22 +560,75 @@ fn assemble_script<B: ByteOrder>(
     }
     let mut result = Vec::new();
 
-    result.write_u32::<B>(jump_entry_count as u32).unwrap();
-    result.append(&mut jump_table_buffer);
+    // all table sizes come first, followed by each table's entries in the same order
+    for (entry_count, _) in &jump_tables {
+        result.write_u32::<B>(*entry_count).unwrap();
+    }
+    for (_, table_buffer) in &mut jump_tables {
+        result.append(table_buffer);
+    }
     result.append(&mut script_buffer);
 
     let result = result;
 
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
     Ok(result)
 }
 
 #[derive(Debug)]
-struct BBSFunction {
-    name: String,
-    args: Vec<ParserValue>,
+pub(crate) struct BBSFunction {
+    pub(crate) name: String,
+    pub(crate) args: Vec<ParserValue>,
+    /// Names given to each argument as `name=value`, empty if the function was not parsed from a readable script
+    pub(crate) arg_names: Vec<Option<String>>,
+    /// Position of the function in a list of parsed instructions, if it wasn't parsed from a readable script
+    index: Option<usize>,
+    /// Bytes of the readable script covered by the function, if it was parsed from one
+    pub(crate) span: Option<Range<usize>>,
+    /// Bytes covered by each argument, empty if the function was not parsed from a readable script
+    pub(crate) arg_spans: Vec<Range<usize>>,
 }
 
 impl BBSFunction {
+    /// Bytes of the readable script covered by the function name
+    fn name_span(&self) -> Option<Range<usize>> {
+        self.span
+            .as_ref()
+            .map(|span| span.start..span.start + self.name.len())
+    }
+
+    /// Bytes of the readable script covered by an argument, falling back to the whole function
+    pub(crate) fn arg_span(&self, index: usize) -> Option<Range<usize>> {
+        self.arg_spans
+            .get(index)
+            .cloned()
+            .or_else(|| self.span.clone())
+    }
+
+    /// Puts the arguments in the given order of their current indices, after which their names are no longer needed
+    fn reorder_args(&mut self, order: &[usize]) {
+        self.args = reordered(std::mem::take(&mut self.args), order);
+        self.arg_spans = reordered(std::mem::take(&mut self.arg_spans), order);
+        self.arg_names = vec![None; self.args.len()];
+    }
+
+    /// Returns `true` if some arguments are still named because they couldn't be put in order
+    fn has_unresolved_arg_names(&self) -> bool {
+        self.arg_names.iter().any(Option::is_some)
+    }
+
+    /// The names defined or referenced by the function, which are its string arguments
+    pub(crate) fn symbol_names(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().filter_map(|arg| match arg {
+            ParserValue::String32(s) => Some(s.0.as_str()),
+            ParserValue::String16(s) => Some(s.0.as_str()),
+            _ => None,
+        })
+    }
+
     pub fn total_size(&self) -> usize {
         const BASE_SIZE: usize = 0x4;
 
@@ -200,8 +652,48 @@ impl BBSFunction {
     }
 }
 
+fn reordered<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+
+    order
+        .iter()
+        .filter_map(|index| items[*index].take())
+        .collect()
+}
+
+impl From<&InstructionValue> for BBSFunction {
+    fn from(instruction: &InstructionValue) -> Self {
+        let args = instruction
+            .args
+            .iter()
+            .map(|arg| match arg {
+                ArgValue::Unknown(data) => ParserValue::Raw(data.to_vec()),
+                ArgValue::Number(num) => ParserValue::Number(*num),
+                ArgValue::String16(s) => ParserValue::String16(s.clone()),
+                ArgValue::String32(s) => ParserValue::String32(s.clone()),
+                ArgValue::AccessedValue(TaggedValue::Literal(val)) => ParserValue::Val(*val),
+                ArgValue::AccessedValue(TaggedValue::Variable(val)) => ParserValue::Mem(*val),
+                ArgValue::AccessedValue(TaggedValue::Improper { tag, value }) => {
+                    ParserValue::BadTag(*tag, *value)
+                }
+                // enums are written as their raw value so unnamed variants are preserved
+                ArgValue::Enum(_, val) => ParserValue::Number(*val),
+            })
+            .collect();
+
+        Self {
+            name: instruction.display_name(),
+            args,
+            arg_names: Vec::new(),
+            index: None,
+            span: None,
+            arg_spans: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
-enum ParserValue {
+pub(crate) enum ParserValue {
     String32(SizedString<32>),
     String16(SizedString<16>),
     Named(String),
@@ -214,24 +706,49 @@ enum ParserValue {
 }
 
 impl ParserValue {
-    pub fn to_arg_type(&self) -> ArgType {
+    /// Returns the arg type the value is written as, for instructions missing from the config.
+    /// Enum variants can't be written without the config saying which enum they belong to
+    pub fn to_arg_type(&self) -> Result<ArgType, BBScriptError> {
         use ArgType::*;
-        match self {
+        Ok(match self {
             ParserValue::String32(_) => String32,
             ParserValue::String16(_) => String16,
-            ParserValue::Named(_) => panic!("this should never happen"),
+            ParserValue::Named(variant) => {
+                return Err(BBScriptError::UntypedEnumVariant(variant.clone()))
+            }
             ParserValue::Number(_) => Number,
             ParserValue::Raw(data) => Unknown(data.len()),
             ParserValue::NamedMem(_) => AccessedValue,
             ParserValue::Mem(_) => AccessedValue,
             ParserValue::Val(_) => AccessedValue,
             ParserValue::BadTag(_, _) => AccessedValue,
+        })
+    }
+}
+
+/// Writes the value the way it is written in readable scripts
+impl std::fmt::Display for ParserValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParserValue::String32(string) => write!(f, "s32'{}'", escaped(&string.0)),
+            ParserValue::String16(string) => write!(f, "s16'{}'", escaped(&string.0)),
+            ParserValue::Named(name) => write!(f, "({name})"),
+            ParserValue::Number(num) => write!(f, "{num}"),
+            ParserValue::Raw(data) => write!(f, "0x{}", hex::encode_upper(data)),
+            ParserValue::NamedMem(name) => write!(f, "Mem({name})"),
+            ParserValue::Mem(id) => write!(f, "Mem({id})"),
+            ParserValue::Val(val) => write!(f, "Val({val})"),
+            ParserValue::BadTag(tag, val) => write!(f, "BadTag({tag}, {val})"),
         }
     }
 }
 
 type Node<'i> = pest_consume::Node<'i, Rule, ()>;
+/// An argument with the name it was given, if any, and the bytes of the readable script it covers
+type ParsedArg = (Option<String>, ParserValue, Range<usize>);
 type PResult<T> = Result<T, pest_consume::Error<Rule>>;
+/// An error in the arguments of an instruction, with the bytes of the script it covers if it's about a single argument
+type ArgOrderError = (BBScriptError, Option<Range<usize>>);
 
 #[derive(Parser)]
 #[grammar = "readable_bbscript.pest"]
@@ -250,28 +767,54 @@ impl BBSParser {
     }
 
     fn function(input: Node) -> PResult<BBSFunction> {
+        let span = Some(input.as_span().start()..input.as_span().end());
         let input = input.into_children();
-        let none = Vec::new();
 
-        let func = match_nodes!(input;
-            [function_name(name), args(args)] => BBSFunction { name, args },
-            [function_name(name)] => BBSFunction { name, args: none }
+        let (name, args) = match_nodes!(input;
+            [function_name(name), args(args)] => (name, args),
+            [function_name(name)] => (name, Vec::new())
         );
 
-        Ok(func)
+        let mut function = BBSFunction {
+            name,
+            args: Vec::new(),
+            arg_names: Vec::new(),
+            index: None,
+            span,
+            arg_spans: Vec::new(),
+        };
+        for (arg_name, arg, arg_span) in args {
+            function.arg_names.push(arg_name);
+            function.args.push(arg);
+            function.arg_spans.push(arg_span);
+        }
+
+        Ok(function)
     }
 
     fn function_name(input: Node) -> PResult<String> {
         Ok(input.as_str().into())
     }
 
-    fn args(input: Node) -> PResult<Vec<ParserValue>> {
+    fn args(input: Node) -> PResult<Vec<ParsedArg>> {
         Ok(match_nodes!(input.into_children();
             [arg(args)..,] => args.collect()
         ))
     }
 
-    fn arg(input: Node) -> PResult<ParserValue> {
+    fn arg(input: Node) -> PResult<ParsedArg> {
+        let span = input.as_span().start()..input.as_span().end();
+        Ok(match_nodes!(input.into_children();
+            [arg_name(name), value(value)] => (Some(name), value, span),
+            [value(value)] => (None, value, span),
+        ))
+    }
+
+    fn arg_name(input: Node) -> PResult<String> {
+        Ok(input.as_str().into())
+    }
+
+    fn value(input: Node) -> PResult<ParserValue> {
         Ok(match_nodes!(input.into_children();
             [string32(string)] => ParserValue::String32(string),
             [string16(string)] => ParserValue::String16(string),
@@ -340,3 +883,254 @@ impl BBSParser {
 fn unescaped<T: AsRef<str>>(string: T) -> String {
     string.as_ref().replace(r"\'", r"'")
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        check_instruction_symbols, rebuild_bbscript, rebuild_bbscript_with_symbols, ExternalSymbols,
+    };
+    use crate::game_config::{ScriptConfig, SymbolKind};
+    use crate::BBScriptError;
+    use byteorder::{ByteOrder, LittleEndian};
+
+    const DNF_SCRIPT: &str = "beginState: s32'StateA'
+  callSubroutine: s32'SubA'
+  endState: 
+
+beginSubroutine: s32'SubA'
+  endSubroutine: 
+
+beginState: s32'StateB'
+  endState: 
+
+";
+
+    #[test]
+    fn rebuild_multiple_jump_tables() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+        let rebuilt = rebuild_bbscript::<LittleEndian>(&config, DNF_SCRIPT.into()).unwrap();
+
+        // one count per table, in config order
+        assert_eq!(LittleEndian::read_u32(&rebuilt[0x0..]), 2);
+        assert_eq!(LittleEndian::read_u32(&rebuilt[0x4..]), 1);
+
+        // state table first, followed by the subroutine table
+        let entry_name = |index: usize| {
+            let start = 0x8 + index * 0x24;
+            String::from_utf8_lossy(&rebuilt[start..start + 0x20])
+                .trim_end_matches('\0')
+                .to_string()
+        };
+        assert_eq!(entry_name(0), "StateA");
+        assert_eq!(entry_name(1), "StateB");
+        assert_eq!(entry_name(2), "SubA");
+
+        let readable = config
+            .parse_to_string::<LittleEndian>(&rebuilt, 12)
+            .unwrap();
+        assert_eq!(readable, DNF_SCRIPT);
+
+        let round_trip = rebuild_bbscript::<LittleEndian>(&config, readable).unwrap();
+        assert_eq!(round_trip, rebuilt);
+    }
+
+    #[test]
+    fn undefined_symbols() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+
+        let script = "beginState: s32'A'\n  callSubroutine: s32'Missing'\nendState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column), (2, 3));
+        assert!(error
+            .to_string()
+            .starts_with("Undefined subroutine `Missing`, if it's defined in another script such as a common script, pass that script with `--symbols-from`"));
+        assert!(matches!(
+            error,
+            BBScriptError::Spanned(inner, _) if matches!(
+                *inner,
+                BBScriptError::UndefinedSymbol(SymbolKind::Subroutine, ref name, None) if name == "Missing"
+            )
+        ));
+
+        let mut external = ExternalSymbols::default();
+        external.insert(SymbolKind::Subroutine, "Missing");
+        let bytes =
+            rebuild_bbscript_with_symbols::<LittleEndian>(&config, script.into(), &external)
+                .unwrap();
+
+        // parsed instructions have no source to point at, so the error names the instruction instead
+        let program = config.parse::<LittleEndian>(&bytes).unwrap();
+        let error =
+            check_instruction_symbols(&config, &program, &ExternalSymbols::default()).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Undefined subroutine `Missing` referenced by the instruction at index 1,"
+        ));
+        assert!(check_instruction_symbols(&config, &program, &external).is_ok());
+
+        // labels are only visible within the state that defines them
+        let script = "beginState: s32'A'
+  beginLabel: s32'Loop'
+  gotoLabel: s32'Loop'
+endState:
+beginState: s32'B'
+  gotoLabel: s32'Loop'
+endState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+        assert!(matches!(
+            error,
+            BBScriptError::Spanned(inner, _)
+                if matches!(*inner, BBScriptError::UndefinedSymbol(SymbolKind::Label, _, None))
+        ));
+    }
+
+    #[test]
+    fn undefined_state_references() {
+        let config = ScriptConfig::load("./static_db/ggst.ron").unwrap();
+
+        // every string arg of an instruction is checked, not just the first
+        let script = "beginState: s32'A'
+  jumpToState: s32'B'
+endState:
+beginState: s32'B'
+  jumpToStateIfNot: s32'A', s32'Missing'
+endState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+        assert!(matches!(
+            error,
+            BBScriptError::Spanned(inner, _) if matches!(
+                *inner,
+                BBScriptError::UndefinedSymbol(SymbolKind::State, ref name, None) if name == "Missing"
+            )
+        ));
+
+        let config = ScriptConfig::load("./static_db/bbcf.ron").unwrap();
+        let script = "startState: s32'A'\n  jumpToState: s32'Missing'\nendState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Undefined state `Missing`, if it's defined in another script"));
+    }
+
+    #[test]
+    fn error_locations() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+
+        let script =
+            "beginState: s32'A'\n  spriteTimeVariable: s32'spr', Mem(NotAVariable)\nendState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into())
+            .unwrap_err()
+            .with_file("test.txt");
+        assert_eq!(
+            error.to_string(),
+            "No variable ID associated with `NotAVariable` in config
+ --> test.txt:2:33
+  |
+2 |   spriteTimeVariable: s32'spr', Mem(NotAVariable)
+  |                                 ^^^^^^^^^^^^^^^^^"
+        );
+
+        let script = "beginState: s32'A'\n\tnotAnInstruction: 1\nendState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("2 | \tnotAnInstruction: 1\n  | \t^^^^^^^^^^^^^^^^"));
+    }
+
+    #[test]
+    fn collect_all_errors() {
+        let config = ScriptConfig::load("./static_db/dnf.ron").unwrap();
+
+        let script = "beginState: s32'A'
+  notAnInstruction: 1
+  callSubroutine: s32'Missing'
+  spriteTimeVariable: s32'spr', Mem(NotAVariable)
+endState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+
+        let BBScriptError::Multiple(errors) = &error else {
+            panic!("expected multiple errors, got {error}");
+        };
+        let lines: Vec<_> = errors.iter().map(|e| e.span().unwrap().line).collect();
+        assert_eq!(lines, [2, 3, 4]);
+        assert!(error.to_string().starts_with("3 errors found\n\n"));
+    }
+
+    #[test]
+    fn keyword_args() {
+        let config = crate::SupportedGame::Ggst.into_config();
+        let options = crate::TextOptions {
+            keyword_args: true,
+            ..Default::default()
+        };
+
+        let script = "beginState: s32'A'\n  sprite: s32'spr', 3\nendState:";
+        let rebuilt = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap();
+        let readable = config
+            .parse_to_string::<LittleEndian>(&rebuilt, options)
+            .unwrap();
+        assert!(readable.contains("  sprite: name=s32'spr', duration=3\n"));
+        assert_eq!(
+            rebuild_bbscript::<LittleEndian>(&config, readable).unwrap(),
+            rebuilt
+        );
+
+        // named args are written in the order of the config
+        let script = "beginState: s32'A'\n  sprite: duration=3, name=s32'spr'\nendState:";
+        assert_eq!(
+            rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap(),
+            rebuilt
+        );
+    }
+
+    #[test]
+    fn bad_keyword_args() {
+        let config = crate::SupportedGame::Ggst.into_config();
+
+        let script = "beginState: s32'A'
+  sprite: duration=3
+  sprite: name=s32'spr', duration=3, name=s32'spr'
+  sprite: name=s32'spr', 3
+  sprite: s32'spr', length=3
+endState:";
+        let error = rebuild_bbscript::<LittleEndian>(&config, script.into()).unwrap_err();
+
+        let BBScriptError::Multiple(errors) = &error else {
+            panic!("expected multiple errors, got {error}");
+        };
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                BBScriptError::Spanned(inner, span) => (span.line, span.column, inner.to_string()),
+                _ => panic!("expected a located error, got {e}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (2, 3, "Arg `name` of instruction `sprite` is missing".into()),
+                (
+                    3,
+                    38,
+                    "Arg `name` of instruction `sprite` is given more than once".into()
+                ),
+                (
+                    4,
+                    26,
+                    "Unnamed args of instruction `sprite` must come before named args".into()
+                ),
+                // the arg the unknown name was probably meant for is reported as missing
+                (
+                    5,
+                    3,
+                    "Arg `duration` of instruction `sprite` is missing".into()
+                ),
+                (
+                    5,
+                    21,
+                    "Instruction `sprite` has no arg named `length`".into()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use byteorder::ByteOrder;
+
+use crate::error::BBScriptError;
+use crate::game_config::ScriptConfig;
+use crate::rebuilder::rebuild_bbscript_unchecked;
+
+/// Indent limit used for the intermediate readable script, indentation has no effect on the rebuilt bytes
+const VERIFY_INDENT_LIMIT: usize = 12;
+
+/// Where in the original file the first differing byte was found
+#[derive(Debug, Clone)]
+pub enum MismatchLocation {
+    /// Within the jump table header that precedes the script
+    JumpTable,
+    /// Within an instruction, `index` is the position of the instruction in the script
+    Instruction { index: usize, name: String },
+    /// Past the end of the original file, the rebuilt file is longer
+    EndOfFile,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub offset: usize,
+    pub location: MismatchLocation,
+    pub original_len: usize,
+    pub rebuilt_len: usize,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "first difference at offset {:#X}", self.offset)?;
+
+        match &self.location {
+            MismatchLocation::JumpTable => write!(f, " in jump table")?,
+            MismatchLocation::Instruction { index, name } => {
+                write!(f, " in instruction {index} `{name}`")?
+            }
+            MismatchLocation::EndOfFile => write!(f, " past end of original file")?,
+        }
+
+        if self.original_len != self.rebuilt_len {
+            write!(
+                f,
+                " (original size {:#X}, rebuilt size {:#X})",
+                self.original_len, self.rebuilt_len
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses `input` to a readable script, rebuilds it, and compares the result against `input`.
+/// Returns `None` if the rebuilt script is byte-for-byte identical to the original
+pub fn verify_round_trip<B: ByteOrder>(
+    config: &ScriptConfig,
+    input: &[u8],
+) -> Result<Option<Mismatch>, BBScriptError> {
+    let readable = config.parse_to_string::<B>(input, VERIFY_INDENT_LIMIT)?;
+    // names referenced from other scripts are fine, only the bytes are being checked
+    let rebuilt = rebuild_bbscript_unchecked::<B>(config, readable)?;
+
+    let offset = match input.iter().zip(rebuilt.iter()).position(|(a, b)| a != b) {
+        Some(offset) => offset,
+        None if input.len() == rebuilt.len() => return Ok(None),
+        None => input.len().min(rebuilt.len()),
+    };
+
+    let location = if offset >= input.len() {
+        MismatchLocation::EndOfFile
+    } else {
+        let instructions = config.parse_with_offsets::<B>(input)?;
+
+        // the last instruction starting at or before the offset contains it
+        match instructions.iter().rposition(|(start, _)| *start <= offset) {
+            Some(index) => {
+                let name = instructions[index].1.display_name();
+
+                MismatchLocation::Instruction { index, name }
+            }
+            None => MismatchLocation::JumpTable,
+        }
+    };
+
+    Ok(Some(Mismatch {
+        offset,
+        location,
+        original_len: input.len(),
+        rebuilt_len: rebuilt.len(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::LittleEndian;
+
+    use super::{verify_round_trip, MismatchLocation};
+    use crate::rebuilder::rebuild_bbscript;
+    use crate::SupportedGame;
+
+    #[test]
+    fn report_mismatch() {
+        let config = SupportedGame::Ggst.into_config();
+        let mut script = rebuild_bbscript::<LittleEndian>(
+            &config,
+            "beginState: s32'A'\n  sprite: s32'a', 3\n  sprite: s32'b', 2\nendState:".into(),
+        )
+        .unwrap();
+        assert!(verify_round_trip::<LittleEndian>(&config, &script)
+            .unwrap()
+            .is_none());
+
+        // nulls in a string are dropped when parsing it, so a byte written into the padding of `b`
+        // is rebuilt right after the `b`
+        let instructions = config.parse_with_offsets::<LittleEndian>(&script).unwrap();
+        let name_offset = instructions[2].0 + 4;
+        script[name_offset + 8] = b'c';
+
+        let mismatch = verify_round_trip::<LittleEndian>(&config, &script)
+            .unwrap()
+            .unwrap();
+        let offset = name_offset + 1;
+        assert_eq!(mismatch.offset, offset);
+        assert!(matches!(
+            mismatch.location,
+            MismatchLocation::Instruction { index: 2, ref name } if name == "sprite"
+        ));
+        assert_eq!(
+            mismatch.to_string(),
+            format!("first difference at offset {offset:#X} in instruction 2 `sprite`")
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use crate::ast::{ScriptBlock, ScriptNode};
+use crate::game_config::{BBSNumber, ScriptConfig, TaggedValue};
+use crate::parser::{ArgValue, InstructionValue};
+use crate::HashMap;
+
+/// An operation of instructions such as `modifyVar` and `ifOperation`.
+///
+/// Configs name operations in an enum with variants such as `ADD` and `IS_EQUAL`,
+/// though only some of them give every operation, and the enum is called differently in each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    IsEqual,
+    IsGreater,
+    IsLesser,
+    IsGreaterOrEqual,
+    IsLesserOrEqual,
+    BitDelete,
+    IsNotEqual,
+    ModEquals0,
+    ModEquals1,
+    ModEquals2,
+    AddDir,
+    Set,
+    SetDir,
+    Percent,
+}
+
+impl Operation {
+    /// Returns the operation with the given enum variant name, such as `ADD` or `IS_GREATER_OR_EQUAL`
+    pub fn from_name(name: &str) -> Option<Self> {
+        use Operation::*;
+        let operation = match name {
+            "ADD" => Add,
+            "SUB" => Sub,
+            "MUL" => Mul,
+            "DIV" => Div,
+            "MOD" => Mod,
+            "AND" => And,
+            "OR" => Or,
+            "BIT_AND" => BitAnd,
+            "BIT_OR" => BitOr,
+            "IS_EQUAL" => IsEqual,
+            "IS_GREATER" => IsGreater,
+            "IS_LESSER" => IsLesser,
+            "IS_GREATER_OR_EQUAL" => IsGreaterOrEqual,
+            "IS_LESSER_OR_EQUAL" => IsLesserOrEqual,
+            "BIT_DELETE" => BitDelete,
+            "IS_NOT_EQUAL" => IsNotEqual,
+            "MOD_EQUALS_0" => ModEquals0,
+            "MOD_EQUALS_1" => ModEquals1,
+            "MOD_EQUALS_2" => ModEquals2,
+            "ADD_DIR" => AddDir,
+            "SET" => Set,
+            "SET_DIR" => SetDir,
+            "PERCENT" => Percent,
+            _ => return None,
+        };
+
+        Some(operation)
+    }
+
+    /// Returns the operation with the given value, as numbered by the ggst, dnf and gbvsr configs.
+    /// Only used for operations that a config doesn't name, as other games may number them differently
+    pub fn from_value(value: BBSNumber) -> Option<Self> {
+        use Operation::*;
+        const OPERATIONS: [Operation; 23] = [
+            Add,
+            Sub,
+            Mul,
+            Div,
+            Mod,
+            And,
+            Or,
+            BitAnd,
+            BitOr,
+            IsEqual,
+            IsGreater,
+            IsLesser,
+            IsGreaterOrEqual,
+            IsLesserOrEqual,
+            BitDelete,
+            IsNotEqual,
+            ModEquals0,
+            ModEquals1,
+            ModEquals2,
+            AddDir,
+            Set,
+            SetDir,
+            Percent,
+        ];
+
+        usize::try_from(value)
+            .ok()
+            .and_then(|index| OPERATIONS.get(index).copied())
+    }
+
+    /// Applies the operation to two values, with comparisons giving `1` if true and `0` otherwise.
+    ///
+    /// Returns `None` for division by zero, and for operations that depend on
+    /// which way the character is facing, which the VM doesn't know
+    pub fn apply(self, a: BBSNumber, b: BBSNumber) -> Option<BBSNumber> {
+        use Operation::*;
+        let result = match self {
+            Add => a.wrapping_add(b),
+            Sub => a.wrapping_sub(b),
+            Mul => a.wrapping_mul(b),
+            Div => a.checked_div(b)?,
+            Mod => a.checked_rem(b)?,
+            And => (a != 0 && b != 0).into(),
+            Or => (a != 0 || b != 0).into(),
+            BitAnd => a & b,
+            BitOr => a | b,
+            IsEqual => (a == b).into(),
+            IsGreater => (a > b).into(),
+            IsLesser => (a < b).into(),
+            IsGreaterOrEqual => (a >= b).into(),
+            IsLesserOrEqual => (a <= b).into(),
+            BitDelete => a & !b,
+            IsNotEqual => (a != b).into(),
+            ModEquals0 => (a.checked_rem(b)? == 0).into(),
+            ModEquals1 => (a.checked_rem(b)? == 1).into(),
+            ModEquals2 => (a.checked_rem(b)? == 2).into(),
+            Set => b,
+            Percent => a.wrapping_mul(b) / 100,
+            AddDir | SetDir => return None,
+        };
+
+        Some(result)
+    }
+}
+
+/// A frame of a state run by a [`StateVm`]
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// 1-based number of the frame within the state
+    pub number: usize,
+    /// Name of the sprite shown on the frame, `None` on the frame the state ends
+    pub sprite: Option<String>,
+    /// Instructions the VM doesn't model that ran on the frame, in the order they ran
+    pub events: Vec<InstructionValue>,
+}
+
+/// Position within the body of a block
+#[derive(Debug, Clone, Copy)]
+struct Cursor<'a> {
+    nodes: &'a [ScriptNode],
+    index: usize,
+    /// Whether the body of the last `if` run in this body was taken, for a following `else`
+    last_condition: Option<bool>,
+}
+
+/// Runs a state, such as one from [`crate::ScriptTree::state`], one frame at a time.
+///
+/// `sprite` instructions show a sprite for their duration in frames, and the instructions after it run
+/// once the duration has passed. `if`, `ifNot`, `ifOperation` and `else` blocks are taken according to the
+/// variable store, which `modifyVar` and `storeValue` write to. Any other instruction is an event that
+/// is logged and returned with the frame it ran on, and the bodies of other blocks are skipped.
+///
+/// Operations are read by their variant name in the config the state was parsed with,
+/// falling back to [`Operation::from_value`] for operations the config doesn't name
+#[derive(Debug, Clone)]
+pub struct StateVm<'a> {
+    config: &'a ScriptConfig,
+    /// Values of variables by the ID they are accessed with, variables missing from it are `0`
+    pub variables: HashMap<BBSNumber, BBSNumber>,
+    stack: Vec<Cursor<'a>>,
+    frame: usize,
+    sprite: Option<String>,
+    /// Frames the current sprite is still shown for after the current frame
+    remaining: usize,
+    finished: bool,
+}
+
+/// What running a single instruction did
+enum Executed {
+    Sprite(String, usize),
+    Modeled,
+    Event,
+}
+
+impl<'a> StateVm<'a> {
+    pub fn new(config: &'a ScriptConfig, state: &'a ScriptBlock) -> Self {
+        Self {
+            config,
+            variables: HashMap::new(),
+            stack: vec![Cursor {
+                nodes: &state.body,
+                index: 0,
+                last_condition: None,
+            }],
+            frame: 0,
+            sprite: None,
+            remaining: 0,
+            finished: false,
+        }
+    }
+
+    /// Sets a variable before running the state, such as to see which branches it takes
+    pub fn with_variable(mut self, id: BBSNumber, value: BBSNumber) -> Self {
+        self.variables.insert(id, value);
+        self
+    }
+
+    pub fn variable(&self, id: BBSNumber) -> BBSNumber {
+        self.variables.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Returns `true` once the end of the state has been reached
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Runs the next frame of the state, returning `None` if the state has already ended
+    pub fn step(&mut self) -> Option<Frame> {
+        if self.finished {
+            return None;
+        }
+        self.frame += 1;
+
+        let mut events = Vec::new();
+        if self.remaining > 0 {
+            self.remaining -= 1;
+        } else {
+            match self.run_until_sprite(&mut events) {
+                Some((sprite, duration)) => {
+                    self.sprite = Some(sprite);
+                    self.remaining = duration - 1;
+                }
+                None => {
+                    self.sprite = None;
+                    self.finished = true;
+                }
+            }
+        }
+
+        Some(Frame {
+            number: self.frame,
+            sprite: self.sprite.clone(),
+            events,
+        })
+    }
+
+    /// Skips the frames the current sprite is still shown for, as no instructions run until it ends,
+    /// returning the number of frames skipped
+    pub fn skip_sprite(&mut self) -> usize {
+        let skipped = std::mem::take(&mut self.remaining);
+        self.frame += skipped;
+        skipped
+    }
+
+    /// Runs instructions until a sprite is shown, returning it with its duration,
+    /// or `None` if the end of the state is reached first
+    fn run_until_sprite(&mut self, events: &mut Vec<InstructionValue>) -> Option<(String, usize)> {
+        loop {
+            let cursor = self.stack.last_mut()?;
+            let nodes = cursor.nodes;
+            let Some(node) = nodes.get(cursor.index) else {
+                self.stack.pop();
+                continue;
+            };
+            cursor.index += 1;
+
+            match node {
+                ScriptNode::Instruction(instruction) => match self.execute(instruction) {
+                    Executed::Sprite(name, duration) => return Some((name, duration)),
+                    Executed::Modeled => {}
+                    Executed::Event => self.log_event(instruction, events),
+                },
+                ScriptNode::Block(block) => {
+                    // an `else` is taken if the `if` before it wasn't, and can't be followed by another
+                    let previous = self.stack.last_mut()?.last_condition.take();
+                    let condition = match block.begin.name.as_deref() {
+                        Some("else") => previous.map(|taken| !taken),
+                        _ => self.condition(&block.begin).inspect(|&taken| {
+                            if let Some(cursor) = self.stack.last_mut() {
+                                cursor.last_condition = Some(taken);
+                            }
+                        }),
+                    };
+
+                    match condition {
+                        Some(true) => self.stack.push(Cursor {
+                            nodes: &block.body,
+                            index: 0,
+                            last_condition: None,
+                        }),
+                        Some(false) => {}
+                        None => self.log_event(&block.begin, events),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the body of a block is taken, or `None` if the block isn't modeled
+    fn condition(&self, begin: &InstructionValue) -> Option<bool> {
+        match (begin.name.as_deref(), begin.args.as_slice()) {
+            (Some("if"), [ArgValue::AccessedValue(value)]) => Some(self.read(value)? != 0),
+            (Some("ifNot"), [ArgValue::AccessedValue(value)]) => Some(self.read(value)? == 0),
+            (
+                Some("ifOperation"),
+                [operation, ArgValue::AccessedValue(a), ArgValue::AccessedValue(b)],
+            ) => {
+                let result = self
+                    .operation(operation)?
+                    .apply(self.read(a)?, self.read(b)?)?;
+                Some(result != 0)
+            }
+            _ => None,
+        }
+    }
+
+    fn execute(&mut self, instruction: &InstructionValue) -> Executed {
+        let modeled = match (instruction.name.as_deref(), instruction.args.as_slice()) {
+            (Some("sprite"), [ArgValue::String32(name), ArgValue::Number(duration), ..]) => {
+                // a sprite is always shown for at least the frame it starts on
+                let duration = usize::try_from(*duration).unwrap_or_default().max(1);
+                return Executed::Sprite(name.0.clone(), duration);
+            }
+            (
+                Some("modifyVar"),
+                [operation, ArgValue::AccessedValue(a), ArgValue::AccessedValue(b)],
+            ) => self
+                .operation(operation)
+                .and_then(|operation| operation.apply(self.read(a)?, self.read(b)?))
+                .and_then(|result| self.write(a, result)),
+            (Some("storeValue"), [ArgValue::AccessedValue(a), ArgValue::AccessedValue(b)]) => {
+                self.read(b).and_then(|value| self.write(a, value))
+            }
+            _ => None,
+        };
+
+        match modeled {
+            Some(()) => Executed::Modeled,
+            None => Executed::Event,
+        }
+    }
+
+    /// Reads the operation of an instruction, which is an enum arg in most configs and a number in others
+    fn operation(&self, arg: &ArgValue) -> Option<Operation> {
+        match arg {
+            ArgValue::Enum(name, value) => {
+                match self
+                    .config
+                    .named_value_maps
+                    .get(name)
+                    .and_then(|variants| variants.get_by_left(value))
+                {
+                    Some(variant) => Operation::from_name(variant),
+                    None => Operation::from_value(*value),
+                }
+            }
+            ArgValue::Number(value) => Operation::from_value(*value),
+            _ => None,
+        }
+    }
+
+    /// Reads a value, or `None` if its tag is neither a literal nor a variable
+    fn read(&self, value: &TaggedValue) -> Option<BBSNumber> {
+        match value {
+            TaggedValue::Literal(value) => Some(*value),
+            TaggedValue::Variable(id) => Some(self.variable(*id)),
+            TaggedValue::Improper { .. } => None,
+        }
+    }
+
+    /// Writes to a variable, or returns `None` if `target` isn't one
+    fn write(&mut self, target: &TaggedValue, value: BBSNumber) -> Option<()> {
+        match target {
+            TaggedValue::Variable(id) => {
+                self.variables.insert(*id, value);
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    fn log_event(&self, instruction: &InstructionValue, events: &mut Vec<InstructionValue>) {
+        log::debug!("frame {}: {}", self.frame, instruction.display_name());
+        events.push(instruction.clone());
+    }
+}
+
+impl Iterator for StateVm<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        self.step()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::LittleEndian;
+
+    use super::{Operation, StateVm};
+    use crate::game_config::ScriptConfig;
+    use crate::rebuilder::rebuild_bbscript;
+    use crate::{ScriptTree, SupportedGame};
+
+    const SCRIPT: &str = "beginState: s32'Test'
+  sprite: s32'a', 2
+  modifyVar: (ADD), Mem(5), Val(3)
+  ifOperation: (IS_EQUAL), Mem(5), Val(3)
+    sprite: s32'b', 1
+  endIf:
+  else:
+    sprite: s32'c', 1
+  endElse:
+  if: Mem(6)
+    sprite: s32'd', 1
+  endIf:
+  ifOpponentCharacter: s16'x'
+    sprite: s32'e', 1
+  endIf:
+  storeValue: Mem(6), Val(1)
+  exitState:
+endState:
+";
+
+    fn tree(config: &ScriptConfig) -> ScriptTree {
+        let bytes = rebuild_bbscript::<LittleEndian>(config, SCRIPT.into()).unwrap();
+        config.parse_tree::<LittleEndian>(bytes).unwrap()
+    }
+
+    fn sprites(vm: StateVm) -> Vec<Option<String>> {
+        vm.map(|frame| frame.sprite).collect()
+    }
+
+    #[test]
+    fn step_frames() {
+        let config = SupportedGame::Ggst.into_config();
+        let tree = tree(&config);
+        let mut vm = StateVm::new(&config, tree.state("Test").unwrap());
+
+        let names = |frame: super::Frame| {
+            (
+                frame.number,
+                frame.sprite,
+                frame
+                    .events
+                    .iter()
+                    .map(|event| event.display_name())
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(names(vm.step().unwrap()), (1, Some("a".into()), vec![]));
+        assert_eq!(names(vm.step().unwrap()), (2, Some("a".into()), vec![]));
+        assert_eq!(names(vm.step().unwrap()), (3, Some("b".into()), vec![]));
+        // the body of a block that isn't modeled is skipped
+        assert_eq!(
+            names(vm.step().unwrap()),
+            (
+                4,
+                None,
+                vec!["ifOpponentCharacter".into(), "exitState".into()]
+            )
+        );
+        assert!(vm.is_finished());
+        assert!(vm.step().is_none());
+
+        assert_eq!(vm.variable(5), 3);
+        assert_eq!(vm.variable(6), 1);
+    }
+
+    #[test]
+    fn skip_sprites() {
+        let config = SupportedGame::Ggst.into_config();
+        let tree = tree(&config);
+        let mut vm = StateVm::new(&config, tree.state("Test").unwrap());
+
+        assert_eq!(vm.step().unwrap().number, 1);
+        assert_eq!(vm.skip_sprite(), 1);
+        let frame = vm.step().unwrap();
+        assert_eq!((frame.number, frame.sprite), (3, Some("b".into())));
+        assert_eq!(vm.skip_sprite(), 0);
+    }
+
+    #[test]
+    fn take_branches() {
+        let config = SupportedGame::Ggst.into_config();
+        let tree = tree(&config);
+        let state = tree.state("Test").unwrap();
+        let sprite = |name: &str| Some(name.to_string());
+
+        assert_eq!(
+            sprites(
+                StateVm::new(&config, state)
+                    .with_variable(5, 1)
+                    .with_variable(6, 1)
+            ),
+            [sprite("a"), sprite("a"), sprite("c"), sprite("d"), None]
+        );
+    }
+
+    #[test]
+    fn operations_by_name() {
+        let mut config = SupportedGame::Ggst.into_config();
+        let tree = tree(&config);
+        let state = tree.state("Test").unwrap();
+
+        // a config numbering operations differently is followed over the usual numbering
+        config
+            .named_value_maps
+            .get_mut("OPERATION")
+            .unwrap()
+            .insert(0, "SET".into());
+        let mut vm = StateVm::new(&config, state).with_variable(5, 1);
+        vm.by_ref().for_each(drop);
+        assert_eq!(vm.variable(5), 3);
+
+        // operations missing from the config use the usual numbering
+        config.named_value_maps.remove("OPERATION");
+        let mut vm = StateVm::new(&config, state).with_variable(5, 1);
+        vm.by_ref().for_each(drop);
+        assert_eq!(vm.variable(5), 4);
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(Operation::from_value(20), Some(Operation::Set));
+        assert_eq!(Operation::from_value(23), None);
+        assert_eq!(
+            Operation::from_name("IS_GREATER_OR_EQUAL"),
+            Some(Operation::IsGreaterOrEqual)
+        );
+        assert_eq!(Operation::from_name("Add"), None);
+
+        assert_eq!(Operation::Sub.apply(2, 5), Some(-3));
+        assert_eq!(Operation::Div.apply(2, 0), None);
+        assert_eq!(Operation::BitDelete.apply(0b111, 0b010), Some(0b101));
+        assert_eq!(Operation::ModEquals1.apply(7, 3), Some(1));
+        assert_eq!(Operation::Percent.apply(50, 30), Some(15));
+        assert_eq!(Operation::AddDir.apply(1, 1), None);
+    }
+}